@@ -0,0 +1,448 @@
+use crate::{Compare, Duplicates, Natural, TreeOpError};
+use std::borrow::Borrow;
+use std::cmp::Ordering;
+use std::marker::PhantomData;
+
+/// Index standing for a missing child.
+const NIL: u32 = u32::MAX;
+
+/// Binary search tree whose nodes are stored in a single `Vec`.
+///
+/// Offers the same operations as `Tree`, but children are `u32` indices into
+/// the arena instead of `Box`es: nodes are allocated contiguously, without a
+/// call to the allocator per insertion, and on 64-bit targets their two child
+/// links take 8 bytes instead of 16. The value and the `usize` count keep
+/// their size. The slots of deleted nodes are chained in a free list and
+/// reused by later insertions.
+pub struct ArenaTree<T, C = Natural> {
+    slots: Vec<Slot<T>>,
+    root: u32,
+    /// First free slot, whose own `Slot::Free` holds the next one.
+    free: u32,
+    len: usize,
+    order: PhantomData<C>,
+}
+
+/// Arena slot, either holding a node or linked in the free list.
+enum Slot<T> {
+    Node(Node<T>),
+    Free(u32),
+}
+
+/// Internal Node representation, see `crate::Node`.
+struct Node<T> {
+    value: T,
+    count: usize,
+    left: u32,
+    right: u32,
+}
+
+/// Location of a child index: the root of the tree or a child of a node.
+#[derive(Clone, Copy)]
+enum Link {
+    Root,
+    Left(u32),
+    Right(u32),
+}
+
+impl<T> ArenaTree<T> {
+    /// Returns an empty tree, ordered by the natural order of `T`.
+    pub fn new() -> Self {
+        Self::with_comparator()
+    }
+
+    /// Returns an empty tree with room for `capacity` nodes.
+    pub fn with_capacity(capacity: usize) -> Self {
+        ArenaTree {
+            slots: Vec::with_capacity(capacity),
+            ..Self::new()
+        }
+    }
+}
+
+impl<T, C> ArenaTree<T, C> {
+    /// Returns an empty tree, ordered by the comparator `C`.
+    pub fn with_comparator() -> Self {
+        ArenaTree {
+            slots: Vec::new(),
+            root: NIL,
+            free: NIL,
+            len: 0,
+            order: PhantomData,
+        }
+    }
+
+    /// Returns the number of distinct values of the tree.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Returns true iff the tree holds no value.
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Removes every value, keeping the memory of the arena.
+    pub fn clear(&mut self) {
+        self.slots.clear();
+        self.root = NIL;
+        self.free = NIL;
+        self.len = 0;
+    }
+
+    /// Returns an iterator over the values of the tree, in increasing order.
+    pub fn iter(&self) -> Iter<'_, T, C> {
+        let mut iter = Iter {
+            tree: self,
+            stack: Vec::new(),
+        };
+        iter.push_left_spine(self.root);
+        iter
+    }
+
+    fn node(&self, i: u32) -> &Node<T> {
+        match self.slots[i as usize] {
+            Slot::Node(ref n) => n,
+            Slot::Free(_) => unreachable!("free slot {} is linked in the tree", i),
+        }
+    }
+
+    fn node_mut(&mut self, i: u32) -> &mut Node<T> {
+        match self.slots[i as usize] {
+            Slot::Node(ref mut n) => n,
+            Slot::Free(_) => unreachable!("free slot {} is linked in the tree", i),
+        }
+    }
+
+    fn get(&self, link: Link) -> u32 {
+        match link {
+            Link::Root => self.root,
+            Link::Left(i) => self.node(i).left,
+            Link::Right(i) => self.node(i).right,
+        }
+    }
+
+    fn set(&mut self, link: Link, child: u32) {
+        match link {
+            Link::Root => self.root = child,
+            Link::Left(i) => self.node_mut(i).left = child,
+            Link::Right(i) => self.node_mut(i).right = child,
+        }
+    }
+
+    /// Stores a new leaf holding `value`, in a free slot if there is one.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the arena already holds `u32::MAX` nodes.
+    fn alloc(&mut self, value: T) -> u32 {
+        let node = Slot::Node(Node {
+            value,
+            count: 1,
+            left: NIL,
+            right: NIL,
+        });
+
+        if self.free != NIL {
+            let i = self.free;
+            self.free = match self.slots[i as usize] {
+                Slot::Free(next) => next,
+                Slot::Node(_) => unreachable!("slot {} is in the free list", i),
+            };
+            self.slots[i as usize] = node;
+            i
+        } else {
+            assert!(self.slots.len() < NIL as usize, "arena is full");
+            self.slots.push(node);
+            (self.slots.len() - 1) as u32
+        }
+    }
+
+    /// Moves the node out of slot `i` and pushes the slot on the free list.
+    fn release(&mut self, i: u32) -> Node<T> {
+        let slot = std::mem::replace(&mut self.slots[i as usize], Slot::Free(self.free));
+        self.free = i;
+        match slot {
+            Slot::Node(n) => n,
+            Slot::Free(_) => unreachable!("slot {} was already free", i),
+        }
+    }
+}
+
+impl<T, C> ArenaTree<T, C>
+where
+    C: Compare<T>,
+{
+    /// Returns the link to the node holding `target` and its index, or the
+    /// empty link where `target` would be inserted and `NIL`.
+    fn find<Q>(&self, target: &Q) -> (Link, u32)
+    where
+        T: Borrow<Q>,
+        C: Compare<Q>,
+        Q: ?Sized,
+    {
+        let mut link = Link::Root;
+        loop {
+            let i = self.get(link);
+            if i == NIL {
+                return (link, i);
+            }
+
+            link = match C::compare(target, self.node(i).value.borrow()) {
+                Ordering::Equal => return (link, i),
+                Ordering::Less => Link::Left(i),
+                Ordering::Greater => Link::Right(i),
+            };
+        }
+    }
+
+    /// Inserts `value` into the tree.
+    /// Returns `TreeOpError::ValueAlreadyExists` with `value` iff an equal
+    /// value was already contained in the tree.
+    pub fn insert(&mut self, value: T) -> Result<(), TreeOpError<T>> {
+        self.insert_with(value, Duplicates::Reject).map(|_| ())
+    }
+
+    /// Inserts `value` into the tree, handling an equal value already
+    /// contained in the tree according to `duplicates`, see `Tree::insert_with`.
+    pub fn insert_with(
+        &mut self,
+        value: T,
+        duplicates: Duplicates,
+    ) -> Result<Option<T>, TreeOpError<T>> {
+        let (link, i) = self.find::<T>(&value);
+        if i == NIL {
+            let leaf = self.alloc(value);
+            self.set(link, leaf);
+            self.len += 1;
+            return Ok(None);
+        }
+
+        let n = self.node_mut(i);
+        match duplicates {
+            Duplicates::Reject => Err(TreeOpError::ValueAlreadyExists(value)),
+            Duplicates::Replace => Ok(Some(std::mem::replace(&mut n.value, value))),
+            Duplicates::Count => {
+                n.count += 1;
+                Ok(None)
+            }
+        }
+    }
+
+    /// Returns the number of occurrences of `target` in the tree.
+    pub fn count<Q>(&self, target: &Q) -> usize
+    where
+        T: Borrow<Q>,
+        C: Compare<Q>,
+        Q: ?Sized,
+    {
+        match self.find(target) {
+            (_, NIL) => 0,
+            (_, i) => self.node(i).count,
+        }
+    }
+
+    /// Returns true iff `value` belongs to the tree.
+    pub fn contains<Q>(&self, target: &Q) -> bool
+    where
+        T: Borrow<Q>,
+        C: Compare<Q>,
+        Q: ?Sized,
+    {
+        self.find(target).1 != NIL
+    }
+
+    /// Deletes `value` from the tree, whatever its number of occurrences.
+    /// When the value is not found the tree, `TreeOpError::NoValue` is returned.
+    pub fn delete<Q>(&mut self, target: &Q) -> Result<(), TreeOpError>
+    where
+        T: Borrow<Q>,
+        C: Compare<Q>,
+        Q: ?Sized,
+    {
+        match self.find(target) {
+            (_, NIL) => Err(TreeOpError::NoValue),
+            (link, i) => {
+                self.remove(link, i);
+                Ok(())
+            }
+        }
+    }
+
+    /// Deletes a single occurrence of `value` from the tree.
+    /// When the value is not found the tree, `TreeOpError::NoValue` is returned.
+    pub fn delete_one<Q>(&mut self, target: &Q) -> Result<(), TreeOpError>
+    where
+        T: Borrow<Q>,
+        C: Compare<Q>,
+        Q: ?Sized,
+    {
+        match self.find(target) {
+            (_, NIL) => Err(TreeOpError::NoValue),
+            (_, i) if self.node(i).count > 1 => {
+                self.node_mut(i).count -= 1;
+                Ok(())
+            }
+            (link, i) => {
+                self.remove(link, i);
+                Ok(())
+            }
+        }
+    }
+
+    /// Removes node `i`, found at `link`, replacing it by its in-order
+    /// predecessor like `Tree::delete`. Returns its value.
+    fn remove(&mut self, link: Link, i: u32) -> T {
+        self.len -= 1;
+        let left = self.node(i).left;
+        if left == NIL {
+            let right = self.node(i).right;
+            self.set(link, right);
+            return self.release(i).value;
+        }
+
+        // Detach the largest node of the left sub-tree
+        let mut max_link = Link::Left(i);
+        let mut max = left;
+        while self.node(max).right != NIL {
+            max_link = Link::Right(max);
+            max = self.node(max).right;
+        }
+        let max_left = self.node(max).left;
+        self.set(max_link, max_left);
+
+        let predecessor = self.release(max);
+        let n = self.node_mut(i);
+        n.count = predecessor.count;
+        std::mem::replace(&mut n.value, predecessor.value)
+    }
+}
+
+impl<T, C> Default for ArenaTree<T, C> {
+    fn default() -> Self {
+        Self::with_comparator()
+    }
+}
+
+/// In-order iterator over the values of an `ArenaTree`.
+pub struct Iter<'a, T, C = Natural> {
+    tree: &'a ArenaTree<T, C>,
+    stack: Vec<u32>,
+}
+
+impl<'a, T, C> Iter<'a, T, C> {
+    /// Pushes node `i` and all its left descendants.
+    fn push_left_spine(&mut self, mut i: u32) {
+        while i != NIL {
+            self.stack.push(i);
+            i = self.tree.node(i).left;
+        }
+    }
+}
+
+impl<'a, T, C> Iterator for Iter<'a, T, C> {
+    type Item = &'a T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let n = self.tree.node(self.stack.pop()?);
+        self.push_left_spine(n.right);
+        Some(&n.value)
+    }
+}
+
+impl<'a, T, C> IntoIterator for &'a ArenaTree<T, C> {
+    type Item = &'a T;
+    type IntoIter = Iter<'a, T, C>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::Tree;
+
+    #[test]
+    fn inserts_and_deletes() {
+        // Inserting in pre-order gives the arena the shape of the `Tree`
+        let mut arena = ArenaTree::new();
+        for v in crate::test::tree(&[15, 10, 20, 8, 12, 18, 30]).pre_order() {
+            arena.insert(*v).expect("Failed to insert");
+        }
+        arena.insert(12).expect_err("Value was already in the tree");
+        assert_eq!(arena.len(), 7);
+        assert!(arena.contains(&18));
+        assert!(!arena.contains(&19));
+
+        // Root with two children, then a leaf, then a node with one child
+        arena.delete(&15).expect("Failed to delete");
+        arena.delete(&8).expect("Failed to delete");
+        arena.delete(&20).expect("Failed to delete");
+        arena.delete(&20).expect_err("Value is not in the tree");
+        assert!(arena.iter().copied().eq([10, 12, 18, 30]));
+        assert_eq!(arena.len(), 4);
+    }
+
+    #[test]
+    fn reuses_free_slots() {
+        let mut arena = ArenaTree::new();
+        for v in 0..10 {
+            arena.insert(v).expect("Failed to insert");
+        }
+        for v in 0..5 {
+            arena.delete(&v).expect("Failed to delete");
+        }
+        for v in 10..15 {
+            arena.insert(v).expect("Failed to insert");
+        }
+        assert_eq!(arena.slots.len(), 10);
+        assert!(arena.iter().copied().eq(5..15));
+    }
+
+    #[test]
+    fn counts_duplicates() {
+        let mut arena = ArenaTree::new();
+        for v in [5, 3, 5, 5] {
+            arena
+                .insert_with(v, Duplicates::Count)
+                .expect("Failed to insert");
+        }
+        assert_eq!(arena.count(&5), 3);
+        arena.delete_one(&5).expect("Failed to delete");
+        assert_eq!(arena.count(&5), 2);
+        arena.delete(&5).expect("Failed to delete");
+        assert_eq!(arena.count(&5), 0);
+    }
+
+    #[test]
+    fn matches_boxed_tree() {
+        let mut arena = ArenaTree::new();
+        let mut bst = Tree::new();
+        // Deterministic pseudo-random operations
+        let mut x: u32 = 12345;
+        for _ in 0..10_000 {
+            x = x.wrapping_mul(1_103_515_245).wrapping_add(12345);
+            let v = (x >> 16) % 500;
+            if x % 3 == 0 {
+                assert_eq!(arena.delete(&v).is_ok(), bst.delete(&v).is_ok());
+            } else {
+                assert_eq!(arena.insert(v).is_ok(), bst.insert(v).is_ok());
+            }
+        }
+        assert!(arena.iter().eq(bst.iter()));
+        assert_eq!(arena.len(), bst.iter().count());
+    }
+
+    #[test]
+    fn inserts_sorted() {
+        let n = 10_000;
+        let mut arena = ArenaTree::with_capacity(n as usize);
+        for i in 0..n {
+            arena.insert(i).expect("Failed to insert");
+        }
+        assert!(arena.iter().copied().eq(0..n));
+        assert!(arena.contains(&(n - 1)));
+    }
+}
@@ -0,0 +1,285 @@
+use crate::TreeOpError;
+use std::borrow::Borrow;
+use std::cmp::Ordering;
+
+/// Self-balancing (AVL) binary search tree.
+///
+/// Exposes the same operations as `Tree`, but restores the AVL invariant after
+/// every mutation: for every node, the heights of the left and right sub-trees
+/// differ by at most one. The height therefore stays in O(log n), even when
+/// values are inserted in sorted order.
+#[derive(Debug)]
+pub struct AvlTree<T>(Option<Box<Node<T>>>);
+
+/// Internal Node representation, caching the `height` of its sub-tree.
+#[derive(Debug)]
+struct Node<T> {
+    value: T,
+    height: usize,
+    left: AvlTree<T>,
+    right: AvlTree<T>,
+}
+
+impl<T> AvlTree<T>
+where
+    T: Ord,
+{
+    /// Returns an empty tree.
+    pub fn new() -> Self {
+        AvlTree(None)
+    }
+
+    /// Returns a tree containing a single value.
+    fn leaf(value: T) -> Self {
+        AvlTree(Some(Box::new(Node {
+            value,
+            height: 1,
+            left: AvlTree(None),
+            right: AvlTree(None),
+        })))
+    }
+
+    /// Returns the number of nodes on the longest path from the root to a leaf.
+    pub fn height(&self) -> usize {
+        self.0.as_ref().map_or(0, |n| n.height)
+    }
+
+    /// Inserts `value` into the tree.
+    /// Returns `TreeOpError::ValueAlreadyExists` with `value` iff an equal
+    /// value was already contained in the tree.
+    pub fn insert(&mut self, value: T) -> Result<(), TreeOpError<T>> {
+        match self.0 {
+            Some(ref mut n) => match value.cmp(&n.value) {
+                Ordering::Equal => return Err(TreeOpError::ValueAlreadyExists(value)),
+                Ordering::Less => n.left.insert(value)?,
+                Ordering::Greater => n.right.insert(value)?,
+            },
+            None => {
+                *self = AvlTree::leaf(value);
+                return Ok(());
+            }
+        }
+
+        self.rebalance();
+        Ok(())
+    }
+
+    /// Returns true iff `value` belongs to the tree.
+    pub fn contains<Q>(&self, target: &Q) -> bool
+    where
+        T: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        let mut current = self;
+        while let Some(ref n) = current.0 {
+            current = match target.cmp(n.value.borrow()) {
+                Ordering::Equal => return true,
+                Ordering::Less => &n.left,
+                Ordering::Greater => &n.right,
+            };
+        }
+
+        false
+    }
+
+    /// Deletes `value` from the tree.
+    /// When the value is not found the tree, `TreeOpError::NoValue` is returned.
+    pub fn delete<Q>(&mut self, target: &Q) -> Result<(), TreeOpError>
+    where
+        T: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        let Node {
+            value, left, right, ..
+        } = match &mut self.0 {
+            Some(n) => n.as_mut(),
+            None => return Err(TreeOpError::NoValue),
+        };
+
+        match target.cmp((*value).borrow()) {
+            Ordering::Equal => match left.take_max() {
+                Some(predecessor) => *value = predecessor,
+                None => {
+                    self.0 = right.0.take();
+                    return Ok(());
+                }
+            },
+            Ordering::Less => left.delete(target)?,
+            Ordering::Greater => right.delete(target)?,
+        }
+
+        self.rebalance();
+        Ok(())
+    }
+
+    /// Removes and returns the largest value of the tree, rebalancing every
+    /// node on the way back to the root.
+    fn take_max(&mut self) -> Option<T> {
+        let n = self.0.as_mut()?;
+        match n.right.take_max() {
+            Some(max) => {
+                self.rebalance();
+                Some(max)
+            }
+            None => {
+                let max = self.0.take().unwrap();
+                self.0 = max.left.0;
+                Some(max.value)
+            }
+        }
+    }
+
+    /// Returns the height of the left sub-tree minus the height of the right one.
+    fn balance_factor(&self) -> isize {
+        self.0
+            .as_ref()
+            .map_or(0, |n| n.left.height() as isize - n.right.height() as isize)
+    }
+
+    fn update_height(&mut self) {
+        if let Some(ref mut n) = self.0 {
+            n.height = 1 + n.left.height().max(n.right.height());
+        }
+    }
+
+    /// Restores the AVL invariant at the root, assuming both sub-trees are
+    /// valid AVL trees whose heights differ by at most two.
+    fn rebalance(&mut self) {
+        self.update_height();
+
+        match self.balance_factor() {
+            2 => {
+                let n = self.0.as_mut().unwrap();
+                if n.left.balance_factor() < 0 {
+                    n.left.rotate_left();
+                }
+                self.rotate_right();
+            }
+            -2 => {
+                let n = self.0.as_mut().unwrap();
+                if n.right.balance_factor() > 0 {
+                    n.right.rotate_right();
+                }
+                self.rotate_left();
+            }
+            _ => {}
+        }
+    }
+
+    /// Rotates the root with its left child:
+    /// ```text
+    ///       y            x
+    ///      / \          / \
+    ///     x   C  ==>   A   y
+    ///    / \              / \
+    ///   A   B            B   C
+    /// ```
+    fn rotate_right(&mut self) {
+        let mut y = self.0.take().expect("rotation of an empty tree");
+        let mut x = y.left.0.take().expect("rotation without left child");
+        y.left = AvlTree(x.right.0.take());
+        x.right = AvlTree(Some(y));
+        x.right.update_height();
+        self.0 = Some(x);
+        self.update_height();
+    }
+
+    /// Mirror of `rotate_right`.
+    fn rotate_left(&mut self) {
+        let mut x = self.0.take().expect("rotation of an empty tree");
+        let mut y = x.right.0.take().expect("rotation without right child");
+        x.right = AvlTree(y.left.0.take());
+        y.left = AvlTree(Some(x));
+        y.left.update_height();
+        self.0 = Some(y);
+        self.update_height();
+    }
+}
+
+impl<T> Default for AvlTree<T>
+where
+    T: Ord,
+{
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    /// Checks the ordering and balance invariants, and the cached heights.
+    /// Returns the height of `tree`.
+    fn check<T: Ord>(tree: &AvlTree<T>, low: Option<&T>, high: Option<&T>) -> usize {
+        let n = match tree.0 {
+            Some(ref n) => n,
+            None => return 0,
+        };
+        assert!(low.map_or(true, |low| *low < n.value));
+        assert!(high.map_or(true, |high| n.value < *high));
+
+        let lh = check(&n.left, low, Some(&n.value));
+        let rh = check(&n.right, Some(&n.value), high);
+        assert!((lh as isize - rh as isize).abs() <= 1);
+        assert_eq!(n.height, 1 + lh.max(rh));
+        n.height
+    }
+
+    /// Upper bound on the height of an AVL tree of `n` nodes.
+    fn max_height(n: usize) -> usize {
+        (1.4405 * ((n + 2) as f64).log2() - 0.3277) as usize
+    }
+
+    #[test]
+    fn inserts() {
+        let mut avl = AvlTree::new();
+        assert!(avl.insert(2).is_ok());
+        assert!(avl.insert(2).is_err());
+        assert!(avl.contains(&2));
+        assert!(!avl.contains(&3));
+    }
+
+    #[test]
+    fn looks_up_borrowed_keys() {
+        let mut avl = AvlTree::new();
+        for word in ["pear", "apple", "fig"] {
+            avl.insert(String::from(word)).expect("Failed to insert");
+        }
+        assert!(avl.contains("fig") && !avl.contains("kiwi"));
+        avl.delete("apple").expect("Failed to delete");
+        assert!(!avl.contains("apple"));
+    }
+
+    #[test]
+    fn remove() {
+        let mut avl = AvlTree::new();
+        avl.insert(2).expect("Failed to insert");
+        avl.insert(23).expect("Failed to insert");
+        avl.insert(20).expect("Failed to insert");
+        assert!(avl.delete(&2).is_ok());
+        assert!(avl.delete(&23).is_ok());
+        assert!(avl.delete(&2).is_err());
+        assert!(avl.contains(&20));
+        check(&avl, None, None);
+    }
+
+    #[test]
+    fn stays_balanced_on_sorted_input() {
+        let n = 100_000;
+        let mut avl = AvlTree::new();
+        for i in 1..=n {
+            avl.insert(i).expect("Failed to insert");
+        }
+        assert_eq!(check(&avl, None, None), avl.height());
+        assert!(avl.height() <= max_height(n));
+        assert!((1..=n).all(|i| avl.contains(&i)));
+
+        for i in (1..=n).filter(|i| i % 3 != 0) {
+            avl.delete(&i).expect("Failed to delete");
+        }
+        check(&avl, None, None);
+        assert!(avl.height() <= max_height(n / 3));
+        assert!((1..=n).all(|i| avl.contains(&i) == (i % 3 == 0)));
+    }
+}
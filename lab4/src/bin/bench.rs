@@ -0,0 +1,83 @@
+use lab4::{ArenaTree, Tree};
+use std::time::{Duration, Instant};
+
+/// Operations measured by the benchmark, common to both trees.
+trait Bench: Default {
+    fn insert(&mut self, key: u32);
+    fn contains(&self, key: u32) -> bool;
+}
+
+impl Bench for Tree<u32> {
+    fn insert(&mut self, key: u32) {
+        Tree::insert(self, key).expect("Failed to insert");
+    }
+
+    fn contains(&self, key: u32) -> bool {
+        Tree::contains(self, &key)
+    }
+}
+
+impl Bench for ArenaTree<u32> {
+    fn insert(&mut self, key: u32) {
+        ArenaTree::insert(self, key).expect("Failed to insert");
+    }
+
+    fn contains(&self, key: u32) -> bool {
+        ArenaTree::contains(self, &key)
+    }
+}
+
+/// Returns `n` distinct keys in a pseudo-random order, so that both trees
+/// stay reasonably balanced.
+fn keys(n: u32) -> Vec<u32> {
+    // Multiplying by an odd constant is a bijection modulo 2^32
+    (0..n).map(|i| i.wrapping_mul(2_654_435_761)).collect()
+}
+
+/// Returns the throughput of `ops` operations in `elapsed`, in millions of
+/// operations per second.
+fn mops(ops: usize, elapsed: Duration) -> f64 {
+    ops as f64 / elapsed.as_secs_f64() / 1e6
+}
+
+/// Times the insertion then the lookup of every key, for `runs` runs.
+/// Returns the mean insert and lookup throughputs.
+fn bench<B: Bench>(keys: &[u32], runs: usize) -> (f64, f64) {
+    let (mut inserts, mut lookups) = (0.0, 0.0);
+    for _ in 0..runs {
+        let mut tree = B::default();
+
+        let t = Instant::now();
+        for &k in keys {
+            tree.insert(k);
+        }
+        inserts += mops(keys.len(), t.elapsed());
+
+        let t = Instant::now();
+        let found = keys.iter().filter(|&&k| tree.contains(k)).count();
+        lookups += mops(keys.len(), t.elapsed());
+        assert_eq!(found, keys.len());
+    }
+
+    (inserts / runs as f64, lookups / runs as f64)
+}
+
+fn main() {
+    let args: Vec<String> = std::env::args().collect();
+    if args.len() < 3 {
+        panic!("Usage: {} <size> <nb_runs>", args[0]);
+    }
+
+    let n = args[1].parse::<u32>().unwrap();
+    let runs = args[2].parse::<usize>().unwrap();
+    if runs == 0 {
+        panic!("<nb_runs> must be at least 1");
+    }
+    let keys = keys(n);
+
+    println!("tree,size,insert_mops,lookup_mops");
+    let (inserts, lookups) = bench::<Tree<u32>>(&keys, runs);
+    println!("boxed,{},{:.3},{:.3}", n, inserts, lookups);
+    let (inserts, lookups) = bench::<ArenaTree<u32>>(&keys, runs);
+    println!("arena,{},{:.3},{:.3}", n, inserts, lookups);
+}
@@ -1,21 +1,30 @@
 use lab4::Tree;
+use std::env;
+use std::error::Error;
 
-fn main() {
-    let mut bst = Tree::new();
+/// Builds a sample tree, or loads the values of the file given as argument
+/// (`-` for stdin), and deletes 20 from it.
+fn main() -> Result<(), Box<dyn Error>> {
+    let mut bst = match env::args().nth(1) {
+        Some(path) => Tree::load(&path).map_err(|e| format!("{}: {}", path, e))?,
+        None => {
+            let mut bst = Tree::new();
+            for value in [15, 10, 20, 8, 12, 18, 30, 16, 19] {
+                bst.insert(value)?;
+            }
+            if let Err(e) = bst.insert(20) {
+                println!("Error: {}", e);
+            }
+            bst
+        }
+    };
+    print!("{}", bst.render());
+    println!("In-order: {:?}", bst.iter().collect::<Vec<_>>());
 
-    bst.insert(15).expect("Failed to insert");
-    bst.insert(10).expect("Failed to insert");
-    bst.insert(20).expect("Failed to insert");
-    bst.insert(20).expect_err("Value was already in the tree");
-    bst.insert(8).expect("Failed to insert");
-    bst.insert(12).expect("Failed to insert");
-    bst.insert(18).expect("Failed to insert");
-    bst.insert(30).expect("Failed to insert");
-    bst.insert(16).expect("Failed to insert");
-    bst.insert(19).expect("Failed to insert");
-    println!("{:#?}", bst);
-
-    bst.delete(&20).expect("Failed to delete");
-    bst.delete(&20).expect_err("Value is not in the tree");
-    println!("{:#?}", bst);
+    if let Err(e) = bst.delete(&20) {
+        println!("Error: {}", e);
+    }
+    print!("{}", bst.render());
+    println!("In-order: {:?}", bst.iter().collect::<Vec<_>>());
+    Ok(())
 }
@@ -0,0 +1,220 @@
+use lab4::Tree;
+use std::error::Error;
+use std::fmt::Write as _;
+use std::fs;
+use std::io::{self, BufRead, IsTerminal, Write};
+
+const HELP: &str = "\
+Commands:
+    insert <value>...    insert values into the tree
+    delete <value>...    delete values from the tree
+    contains <value>     tell whether a value is in the tree
+    print                draw the tree
+    range <low> <high>   list the values between low and high, included
+    load <file>          replace the tree by a balanced one holding the
+                         values of file, separated by blanks, see Tree::load
+    save <file>          save the values of the tree to file, for load
+    restore <file>       replace the tree by the one dumped to file
+    dump <file>          save the tree to file, keeping its exact shape
+    help                 show this message
+    quit                 leave the shell
+Blank lines and lines starting with # are ignored.";
+
+/// Interactive shell over a `Tree<i64>`.
+///
+/// Commands are read line by line from stdin, so that a script can be
+/// replayed with `shell < script`. A failing command is reported on stderr
+/// and does not stop the shell.
+fn main() -> Result<(), Box<dyn Error>> {
+    let stdin = io::stdin();
+    let interactive = stdin.is_terminal();
+    let mut out = io::stdout();
+    let mut bst = Tree::new();
+
+    let mut lines = stdin.lock().lines();
+    loop {
+        if interactive {
+            write!(out, "> ")?;
+            out.flush()?;
+        }
+        let line = match lines.next() {
+            Some(line) => line?,
+            None => break,
+        };
+
+        match run(&mut bst, &line, &mut out) {
+            Ok(true) => {}
+            Ok(false) => break,
+            Err(e) => eprintln!("Error: {}", e),
+        }
+    }
+
+    Ok(())
+}
+
+/// Runs the command of `line` on `bst`, writing its output to `out`.
+/// Returns false iff the shell must stop.
+fn run(bst: &mut Tree<i64>, line: &str, out: &mut impl Write) -> Result<bool, Box<dyn Error>> {
+    let mut words = line.split_whitespace();
+    let command = match words.next() {
+        None => return Ok(true),
+        Some(word) if word.starts_with('#') => return Ok(true),
+        Some(word) => word,
+    };
+    let args: Vec<&str> = words.collect();
+
+    match (command, args.as_slice()) {
+        ("insert", values) if !values.is_empty() => {
+            for value in parse_all(values)? {
+                bst.insert(value)?;
+            }
+        }
+        ("delete", values) if !values.is_empty() => {
+            for value in parse_all(values)? {
+                bst.delete(&value)?;
+            }
+        }
+        ("contains", [value]) => writeln!(out, "{}", bst.contains(&value.parse()?))?,
+        ("print", []) => write!(out, "{}", bst.render())?,
+        ("range", [low, high]) => {
+            let (low, high): (i64, i64) = (low.parse()?, high.parse()?);
+            let values: Vec<_> = bst.range(low..=high).collect();
+            writeln!(out, "{:?}", values)?;
+        }
+        ("load", [file]) => *bst = Tree::load(file).map_err(|e| format!("{}: {}", file, e))?,
+        ("save", [file]) => {
+            let mut text = String::new();
+            for value in bst.iter() {
+                writeln!(text, "{}", value)?;
+            }
+            fs::write(file, text)?;
+        }
+        ("restore", [file]) => {
+            let text = fs::read_to_string(file)?;
+            *bst = Tree::from_text(&text).map_err(|e| format!("{}: {}", file, e))?;
+        }
+        ("dump", [file]) => fs::write(file, bst.to_text())?,
+        ("help", []) => writeln!(out, "{}", HELP)?,
+        ("quit", []) | ("exit", []) => return Ok(false),
+        _ => return Err(format!("invalid command `{}`, see `help`", line.trim()).into()),
+    }
+
+    Ok(true)
+}
+
+/// Parses every value of `words`, before any of them is used.
+fn parse_all(words: &[&str]) -> Result<Vec<i64>, Box<dyn Error>> {
+    words
+        .iter()
+        .map(|w| w.parse().map_err(|e| format!("`{}`: {}", w, e).into()))
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::path::PathBuf;
+
+    /// Runs the commands of `script` on `bst`, returning the output and the
+    /// error messages.
+    fn replay(bst: &mut Tree<i64>, script: &str) -> (String, Vec<String>) {
+        let (mut out, mut errors) = (Vec::new(), Vec::new());
+        for line in script.lines() {
+            match run(bst, line, &mut out) {
+                Ok(true) => {}
+                Ok(false) => break,
+                Err(e) => errors.push(e.to_string()),
+            }
+        }
+        (String::from_utf8(out).unwrap(), errors)
+    }
+
+    fn temp_path(name: &str) -> PathBuf {
+        std::env::temp_dir().join(format!("lab4-{}-{}.txt", std::process::id(), name))
+    }
+
+    #[test]
+    fn runs_scripts() {
+        let mut bst = Tree::new();
+        let script = "\
+# Comments and blank lines are skipped
+
+insert 15 10 20
+contains 10
+contains 11
+delete 10
+range 0 20
+print
+quit
+insert 1";
+        let (out, errors) = replay(&mut bst, script);
+        assert_eq!(out, "true\nfalse\n[15, 20]\n    ┌── 20\n15\n");
+        assert!(errors.is_empty());
+        assert!(bst.iter().copied().eq([15, 20]));
+    }
+
+    #[test]
+    fn reports_errors() {
+        let mut bst = Tree::new();
+        let script = "insert 1\ninsert 1\ndelete 2\ninsert 3 x\ncontains\nfly away";
+        let (out, errors) = replay(&mut bst, script);
+        assert_eq!(out, "");
+        assert_eq!(
+            errors,
+            [
+                "value 1 already exists in the tree",
+                "value not found in the tree",
+                "`x`: invalid digit found in string",
+                "invalid command `contains`, see `help`",
+                "invalid command `fly away`, see `help`",
+            ]
+        );
+        // Nothing is inserted when a value is invalid
+        assert!(bst.iter().copied().eq([1]));
+
+        let (_, errors) = replay(&mut bst, "restore /nonexistent/tree.txt");
+        assert_eq!(errors.len(), 1);
+    }
+
+    #[test]
+    fn saves_and_loads() {
+        let (values, tree) = (temp_path("values"), temp_path("tree"));
+        let mut bst = Tree::new();
+        let script = format!(
+            "insert 1 2 3 4\nsave {}\ndump {}",
+            values.display(),
+            tree.display()
+        );
+        replay(&mut bst, &script);
+        assert_eq!(fs::read_to_string(&values).unwrap(), "1\n2\n3\n4\n");
+
+        // Loading balances the tree, restoring keeps its shape
+        let (out, _) = replay(&mut bst, &format!("load {}\nprint", values.display()));
+        assert_eq!(out, "    ┌── 4\n3\n    └── 2\n        └── 1\n");
+        let (out, _) = replay(&mut bst, &format!("restore {}\nprint", tree.display()));
+        assert_eq!(out.lines().next(), Some("            ┌── 4"));
+
+        fs::write(&values, "1\n2 x\n").unwrap();
+        let (_, errors) = replay(&mut bst, &format!("load {}", values.display()));
+        assert_eq!(
+            errors,
+            [format!(
+                "{}: 2:3: invalid value `x`: invalid digit found in string",
+                values.display()
+            )]
+        );
+        fs::write(&tree, "1\n").unwrap();
+        let (_, errors) = replay(&mut bst, &format!("restore {}", tree.display()));
+        assert_eq!(
+            errors,
+            [format!(
+                "{}: input ends before the tree is complete",
+                tree.display()
+            )]
+        );
+
+        for p in [values, tree] {
+            fs::remove_file(p).unwrap();
+        }
+    }
+}
@@ -0,0 +1,191 @@
+use crate::{Compare, Node, Tree};
+use std::cmp::Ordering;
+use std::iter::FromIterator;
+use std::marker::PhantomData;
+
+/// Bulk construction of balanced `Tree`s.
+impl<T, C> Tree<T, C>
+where
+    C: Compare<T>,
+{
+    /// Builds a perfectly balanced tree from `values`, in O(n).
+    ///
+    /// # Panics
+    ///
+    /// Panics if `values` are not sorted in strictly increasing order.
+    pub fn from_sorted<I>(values: I) -> Self
+    where
+        I: IntoIterator<Item = T>,
+    {
+        let values: Vec<T> = values.into_iter().collect();
+        assert!(
+            values
+                .windows(2)
+                .all(|w| C::compare(&w[0], &w[1]) == Ordering::Less),
+            "values are not sorted in strictly increasing order"
+        );
+
+        let len = values.len();
+        Self::build_balanced(&mut values.into_iter(), len)
+    }
+
+    /// Rebuilds the tree into a perfectly balanced one, in O(n).
+    ///
+    /// The nodes are moved, not rebuilt: values keep their multiplicity (see
+    /// `Duplicates::Count`).
+    pub fn rebalance(&mut self) {
+        // Detach the nodes in order, without recursion
+        let mut nodes = Vec::new();
+        let mut stack: Vec<Box<Node<T, C>>> = Vec::new();
+        let mut current = self.0.take();
+        loop {
+            while let Some(mut n) = current {
+                current = n.left.0.take();
+                stack.push(n);
+            }
+            match stack.pop() {
+                Some(mut n) => {
+                    current = n.right.0.take();
+                    nodes.push(n);
+                }
+                None => break,
+            }
+        }
+
+        let len = nodes.len();
+        *self = Self::link_balanced(&mut nodes.into_iter(), len);
+    }
+}
+
+impl<T, C> Tree<T, C> {
+    /// Builds a perfectly balanced tree from the first `len` values of
+    /// `values`, which must be sorted in increasing order.
+    ///
+    /// Nodes are created in order, so this takes O(len) time; the recursion
+    /// depth is the height of the result, O(log len).
+    pub(crate) fn build_balanced<I>(values: &mut I, len: usize) -> Self
+    where
+        I: Iterator<Item = T>,
+    {
+        let mut nodes = values.map(|value| {
+            Box::new(Node {
+                value,
+                count: 1,
+                left: Tree(None),
+                right: Tree(None),
+                order: PhantomData,
+            })
+        });
+        Self::link_balanced(&mut nodes, len)
+    }
+
+    /// Links the first `len` nodes of `nodes`, which must be sorted in
+    /// increasing order, into a perfectly balanced tree, like
+    /// `build_balanced`. Their children are replaced.
+    fn link_balanced<I>(nodes: &mut I, len: usize) -> Self
+    where
+        I: Iterator<Item = Box<Node<T, C>>>,
+    {
+        if len == 0 {
+            return Tree(None);
+        }
+
+        let left = Self::link_balanced(nodes, len / 2);
+        let mut node = nodes.next().expect("fewer values than announced");
+        let right = Self::link_balanced(nodes, len - len / 2 - 1);
+        node.left = left;
+        node.right = right;
+        Tree(Some(node))
+    }
+}
+
+impl<T, C> FromIterator<T> for Tree<T, C>
+where
+    C: Compare<T>,
+{
+    /// Builds a balanced tree from `iter`, in O(n log n) whatever the order of
+    /// the values. Duplicates are ignored, as they would be by `insert`.
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        let mut values: Vec<T> = iter.into_iter().collect();
+        values.sort_by(C::compare);
+        values.dedup_by(|a, b| C::compare(a, b) == Ordering::Equal);
+
+        let len = values.len();
+        Self::build_balanced(&mut values.into_iter(), len)
+    }
+}
+
+impl<T, C> Extend<T> for Tree<T, C>
+where
+    C: Compare<T>,
+{
+    /// Inserts every value of `iter`, ignoring those already in the tree.
+    fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+        for value in iter {
+            let _ = self.insert(value);
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::Duplicates;
+
+    #[test]
+    fn builds_from_sorted() {
+        let bst: Tree<i32> = Tree::from_sorted(0..1023);
+        assert!(bst.iter().copied().eq(0..1023));
+        assert_eq!(bst.height(), 10);
+        assert_eq!(Tree::<i32>::from_sorted(vec![]), Tree::new());
+    }
+
+    #[test]
+    #[should_panic(expected = "not sorted")]
+    fn refuses_unsorted() {
+        Tree::<i32>::from_sorted(vec![1, 3, 2]);
+    }
+
+    #[test]
+    fn collects() {
+        let bst: Tree<i32> = vec![5, 3, 9, 1, 3, 7].into_iter().collect();
+        assert_eq!(bst.iter().copied().collect::<Vec<_>>(), vec![1, 3, 5, 7, 9]);
+        assert_eq!(bst.height(), 3);
+    }
+
+    #[test]
+    fn extends() {
+        let mut bst: Tree<i32> = Tree::from_sorted(vec![2, 4]);
+        bst.checked(|t| t.extend(vec![3, 4, 1]));
+        assert_eq!(bst.iter().copied().collect::<Vec<_>>(), vec![1, 2, 3, 4]);
+    }
+
+    #[test]
+    fn rebalances() {
+        let mut bst = Tree::new();
+        for i in 0..100 {
+            bst.checked(|t| t.insert(i)).expect("Failed to insert");
+        }
+        assert_eq!(bst.height(), 100);
+
+        bst.checked(|t| t.rebalance());
+        assert!(bst.iter().copied().eq(0..100));
+        assert_eq!(bst.height(), 7);
+    }
+
+    #[test]
+    fn rebalances_counted_values() {
+        let mut bst = Tree::new();
+        for i in 0..20 {
+            for _ in 0..=(i % 3) {
+                bst.checked(|t| t.insert_with(i, Duplicates::Count))
+                    .expect("Failed to insert");
+            }
+        }
+        assert_eq!(bst.height(), 20);
+
+        bst.checked(|t| t.rebalance());
+        assert_eq!(bst.height(), 5);
+        assert!((0..20).all(|i| bst.count(&i) == i % 3 + 1));
+    }
+}
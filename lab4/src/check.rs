@@ -0,0 +1,378 @@
+use crate::{Compare, Node, Tree};
+use std::cmp::Ordering;
+use std::fmt;
+
+/// First violation of the invariants of a `Tree`, found by `Tree::validate`.
+///
+/// Nodes are designated by their path from the root, as a sequence of `L`
+/// (left child) and `R` (right child), the root being the empty path.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Violation {
+    /// The node at `path` lies in the left sub-tree of its `ancestor` but its
+    /// value is not smaller.
+    NotSmaller { path: String, ancestor: String },
+    /// The node at `path` lies in the right sub-tree of its `ancestor` but its
+    /// value is not larger.
+    NotLarger { path: String, ancestor: String },
+    /// The node at `path` holds a value occurring 0 times.
+    ZeroCount { path: String },
+}
+
+impl fmt::Display for Violation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let node = |path: &str| {
+            if path.is_empty() {
+                String::from("the root")
+            } else {
+                format!("node {}", path)
+            }
+        };
+
+        match self {
+            Violation::NotSmaller { path, ancestor } => write!(
+                f,
+                "{} is in the left sub-tree of {} but is not smaller",
+                node(path),
+                node(ancestor)
+            ),
+            Violation::NotLarger { path, ancestor } => write!(
+                f,
+                "{} is in the right sub-tree of {} but is not larger",
+                node(path),
+                node(ancestor)
+            ),
+            Violation::ZeroCount { path } => write!(f, "{} has a count of 0", node(path)),
+        }
+    }
+}
+
+/// Shape statistics of a `Tree`, returned by `Tree::stats`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Stats {
+    /// Number of distinct values, i.e. of nodes.
+    pub len: usize,
+    /// Number of nodes on the longest path from the root, 0 when empty.
+    pub height: usize,
+    /// Number of nodes without children.
+    pub leaf_count: usize,
+    /// Largest absolute balance factor of a node, the balance factor being
+    /// the height of its left sub-tree minus the height of its right one.
+    pub max_imbalance: usize,
+    /// Mean absolute balance factor of the nodes, 0 when empty.
+    pub mean_imbalance: f64,
+}
+
+/// Node on the path from the root to the node being checked by `validate`.
+struct Frame<'a, T, C> {
+    node: &'a Node<T, C>,
+    /// Side of the parent through which the node was reached.
+    side: Option<Ordering>,
+    /// Indices in the path of the closest ancestors whose value bounds the
+    /// node from below and from above.
+    low: Option<usize>,
+    high: Option<usize>,
+    /// Next child to visit: `Less` for the left one, `Greater` for the right
+    /// one, `Equal` when both were visited.
+    next: Ordering,
+}
+
+/// Invariant checks and statistics.
+///
+/// All of them walk the tree once with an explicit stack, in O(n) time, and
+/// handle degenerate trees.
+impl<T, C> Tree<T, C>
+where
+    C: Compare<T>,
+{
+    /// Checks that the values are ordered and that every multiplicity is
+    /// positive. Returns the first violation in pre-order.
+    pub fn validate(&self) -> Result<(), Violation> {
+        let mut path: Vec<Frame<T, C>> = Vec::new();
+        // Next node to enter, with its side, low and high bounds, see `Frame`
+        let mut pending: Option<(_, _, Option<usize>, Option<usize>)> =
+            self.0.as_deref().map(|n| (n, None, None, None));
+
+        loop {
+            // Enter the pending node, checking it against its bounds
+            if let Some((node, side, low, high)) = pending.take() {
+                // Paths are only built on error, they are O(height) long
+                let here = |path: &[Frame<T, C>]| Self::path_of(path, side);
+                if node.count == 0 {
+                    return Err(Violation::ZeroCount { path: here(&path) });
+                }
+                if let Some(h) = high {
+                    if C::compare(&node.value, &path[h].node.value) != Ordering::Less {
+                        let ancestor = Self::path_of(&path[..h], path[h].side);
+                        return Err(Violation::NotSmaller {
+                            path: here(&path),
+                            ancestor,
+                        });
+                    }
+                }
+                if let Some(l) = low {
+                    if C::compare(&node.value, &path[l].node.value) != Ordering::Greater {
+                        let ancestor = Self::path_of(&path[..l], path[l].side);
+                        return Err(Violation::NotLarger {
+                            path: here(&path),
+                            ancestor,
+                        });
+                    }
+                }
+
+                path.push(Frame {
+                    node,
+                    side,
+                    low,
+                    high,
+                    next: Ordering::Less,
+                });
+            }
+
+            // Move to the next child of the deepest node, or back up
+            let i = match path.len() {
+                0 => return Ok(()),
+                len => len - 1,
+            };
+            let frame = &mut path[i];
+            let (child, side) = match frame.next {
+                Ordering::Less => {
+                    frame.next = Ordering::Greater;
+                    (&frame.node.left, Ordering::Less)
+                }
+                Ordering::Greater => {
+                    frame.next = Ordering::Equal;
+                    (&frame.node.right, Ordering::Greater)
+                }
+                Ordering::Equal => {
+                    path.pop();
+                    continue;
+                }
+            };
+            let (low, high) = if side == Ordering::Less {
+                (frame.low, Some(i))
+            } else {
+                (Some(i), frame.high)
+            };
+            pending = child.0.as_deref().map(|n| (n, Some(side), low, high));
+        }
+    }
+
+    /// Returns the path of the node reached through `side` from the last node
+    /// of `ancestors`.
+    fn path_of(ancestors: &[Frame<T, C>], side: Option<Ordering>) -> String {
+        ancestors
+            .iter()
+            .map(|f| f.side)
+            .chain(Some(side))
+            .flatten()
+            .map(|side| if side == Ordering::Less { 'L' } else { 'R' })
+            .collect()
+    }
+}
+
+/// Invariant checks of the tests, after each mutation.
+#[cfg(test)]
+impl<T, C> Tree<T, C>
+where
+    C: Compare<T>,
+{
+    /// Applies the mutation `op` to the tree, then panics if it left the tree
+    /// invalid, see `validate`.
+    pub(crate) fn checked<R>(&mut self, op: impl FnOnce(&mut Self) -> R) -> R {
+        let result = op(self);
+        if let Err(violation) = self.validate() {
+            panic!("invalid tree: {}", violation);
+        }
+        result
+    }
+}
+
+/// Size and shape queries. Nothing is cached in the nodes: except for
+/// `is_empty`, each of them walks the whole tree.
+impl<T, C> Tree<T, C> {
+    /// Returns the number of distinct values of the tree. Multiplicities are
+    /// given by `count`.
+    ///
+    /// The size is not stored: this walks the whole tree, in O(n) time.
+    pub fn len(&self) -> usize {
+        self.iter().count()
+    }
+
+    /// Returns true iff the tree holds no value.
+    pub fn is_empty(&self) -> bool {
+        self.0.is_none()
+    }
+
+    /// Returns the number of nodes on the longest path from the root.
+    pub fn height(&self) -> usize {
+        self.stats().height
+    }
+
+    /// Returns the number of nodes without children.
+    pub fn leaf_count(&self) -> usize {
+        self.stats().leaf_count
+    }
+
+    /// Returns the shape statistics of the tree.
+    pub fn stats(&self) -> Stats {
+        // Post-order walk: the heights of both sub-trees of a node are on top
+        // of `heights` once they have been visited
+        let mut stack = self
+            .0
+            .as_deref()
+            .map(|n| (n, false))
+            .into_iter()
+            .collect::<Vec<_>>();
+        let mut heights: Vec<usize> = Vec::new();
+        let mut stats = Stats {
+            len: 0,
+            height: 0,
+            leaf_count: 0,
+            max_imbalance: 0,
+            mean_imbalance: 0.0,
+        };
+        let mut total_imbalance = 0;
+
+        while let Some((n, expanded)) = stack.pop() {
+            if !expanded {
+                stack.push((n, true));
+                stack.extend(n.right.0.as_deref().map(|r| (r, false)));
+                stack.extend(n.left.0.as_deref().map(|l| (l, false)));
+                continue;
+            }
+
+            let right = if n.right.0.is_some() {
+                heights.pop().unwrap()
+            } else {
+                0
+            };
+            let left = if n.left.0.is_some() {
+                heights.pop().unwrap()
+            } else {
+                0
+            };
+            let imbalance = left.abs_diff(right);
+
+            stats.len += 1;
+            if left == 0 && right == 0 {
+                stats.leaf_count += 1;
+            }
+            stats.max_imbalance = stats.max_imbalance.max(imbalance);
+            total_imbalance += imbalance;
+            heights.push(left.max(right) + 1);
+        }
+
+        stats.height = heights.pop().unwrap_or(0);
+        if stats.len > 0 {
+            stats.mean_imbalance = total_imbalance as f64 / stats.len as f64;
+        }
+        stats
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::marker::PhantomData;
+
+    fn node(value: i32, left: Tree<i32>, right: Tree<i32>) -> Tree<i32> {
+        Tree(Some(Box::new(Node {
+            value,
+            count: 1,
+            left,
+            right,
+            order: PhantomData,
+        })))
+    }
+
+    fn leaf(value: i32) -> Tree<i32> {
+        node(value, Tree(None), Tree(None))
+    }
+
+    #[test]
+    fn validates() {
+        let bst = crate::test::tree(&[15, 10, 20, 8, 12, 18, 30]);
+        assert_eq!(bst.validate(), Ok(()));
+        assert_eq!(Tree::<i32>::new().validate(), Ok(()));
+    }
+
+    #[test]
+    fn reports_first_violation() {
+        // 16 is in the left sub-tree of the root 15
+        let bst = node(
+            15,
+            node(10, leaf(8), leaf(16)),
+            node(20, leaf(14), Tree(None)),
+        );
+        let violation = bst.validate().unwrap_err();
+        assert_eq!(
+            violation,
+            Violation::NotSmaller {
+                path: String::from("LR"),
+                ancestor: String::new(),
+            }
+        );
+        assert_eq!(
+            violation.to_string(),
+            "node LR is in the left sub-tree of the root but is not smaller"
+        );
+
+        let bst = node(15, leaf(10), node(20, leaf(14), Tree(None)));
+        assert_eq!(
+            bst.validate(),
+            Err(Violation::NotLarger {
+                path: String::from("RL"),
+                ancestor: String::new(),
+            })
+        );
+
+        // Equal values are violations too
+        let bst = node(15, leaf(10), node(20, Tree(None), leaf(20)));
+        assert_eq!(
+            bst.validate().unwrap_err().to_string(),
+            "node RR is in the right sub-tree of node R but is not larger"
+        );
+
+        let mut bst = leaf(1);
+        bst.0.as_mut().unwrap().count = 0;
+        assert_eq!(
+            bst.validate(),
+            Err(Violation::ZeroCount {
+                path: String::new()
+            })
+        );
+    }
+
+    #[test]
+    fn computes_stats() {
+        // 15 -> (10 -> 8, 12 -> 11), 20
+        let bst = node(
+            15,
+            node(10, leaf(8), node(12, leaf(11), Tree(None))),
+            leaf(20),
+        );
+        assert_eq!(
+            bst.stats(),
+            Stats {
+                len: 6,
+                height: 4,
+                leaf_count: 3,
+                max_imbalance: 2,
+                mean_imbalance: 4.0 / 6.0,
+            }
+        );
+        assert_eq!((bst.len(), bst.height(), bst.leaf_count()), (6, 4, 3));
+        assert!(!bst.is_empty() && Tree::<i32>::new().is_empty());
+        assert_eq!(Tree::<i32>::new().stats().height, 0);
+    }
+
+    #[test]
+    fn handles_degenerate_trees() {
+        let bst = crate::test::sorted_chain(1_000_000);
+        assert_eq!(bst.validate(), Ok(()));
+
+        let stats = bst.stats();
+        assert_eq!((stats.height, stats.leaf_count), (1_000_000, 1));
+        assert_eq!(stats.max_imbalance, 999_999);
+    }
+}
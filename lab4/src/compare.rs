@@ -0,0 +1,91 @@
+use std::cmp::Ordering;
+
+/// Total order used by a `Tree` to arrange its values.
+///
+/// Comparators are types rather than values: a `Tree<T, C>` only stores `C`
+/// in its type, so every tree using the same comparator agrees on the order.
+/// Implementing `Compare<Q>` for borrowed forms `Q` of the values (e.g. `str`
+/// for `String`) allows looking values up without building a `T`, which must
+/// then order `Q`s consistently with `T`s.
+pub trait Compare<T: ?Sized> {
+    /// Returns the ordering of `a` relative to `b`.
+    fn compare(a: &T, b: &T) -> Ordering;
+}
+
+/// The natural order of `Ord` values, used by default.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Natural;
+
+impl<T> Compare<T> for Natural
+where
+    T: Ord + ?Sized,
+{
+    fn compare(a: &T, b: &T) -> Ordering {
+        a.cmp(b)
+    }
+}
+
+/// The reverse of the natural order of `Ord` values.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Reverse;
+
+impl<T> Compare<T> for Reverse
+where
+    T: Ord + ?Sized,
+{
+    fn compare(a: &T, b: &T) -> Ordering {
+        b.cmp(a)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::Tree;
+    use std::ops::Bound;
+
+    /// Orders strings ignoring ASCII case, for `String`s and `str`s alike.
+    #[derive(Debug)]
+    struct CaseInsensitive;
+
+    impl<S> Compare<S> for CaseInsensitive
+    where
+        S: AsRef<str> + ?Sized,
+    {
+        fn compare(a: &S, b: &S) -> Ordering {
+            let a = a.as_ref().bytes().map(|c| c.to_ascii_lowercase());
+            let b = b.as_ref().bytes().map(|c| c.to_ascii_lowercase());
+            a.cmp(b)
+        }
+    }
+
+    #[test]
+    fn looks_up_borrowed_values() {
+        let mut bst = Tree::new();
+        for s in ["kiwi", "apple", "fig"] {
+            bst.checked(|t| t.insert(String::from(s)))
+                .expect("Failed to insert");
+        }
+        assert!(bst.contains("fig"));
+        assert!(!bst.contains("pear"));
+        bst.checked(|t| t.delete("kiwi")).expect("Failed to delete");
+        assert_eq!(bst.count("kiwi"), 0);
+    }
+
+    #[test]
+    fn orders_with_comparator() {
+        let mut bst = Tree::<String, CaseInsensitive>::with_comparator();
+        for s in ["banana", "Cherry", "apple", "BANANA"] {
+            let _ = bst.checked(|t| t.insert(String::from(s)));
+        }
+        assert!(bst.iter().eq(["apple", "banana", "Cherry"]));
+        assert!(bst.contains("APPLE"));
+        assert_eq!(bst.floor("bz").map(String::as_str), Some("banana"));
+
+        let bst: Tree<i32, Reverse> = (1..=5).collect();
+        assert!(bst.iter().copied().eq((1..=5).rev()));
+        // Bounds follow the order of the tree
+        let range = (Bound::Included(4), Bound::Excluded(1));
+        assert!(bst.range(range).copied().eq([4, 3, 2]));
+    }
+}
@@ -0,0 +1,210 @@
+use crate::{Compare, Natural, PersistentTree, TreeOpError};
+use std::borrow::Borrow;
+use std::sync::{Mutex, PoisonError, RwLock};
+
+/// Thread-safe tree for read-mostly workloads, shared as `&ConcurrentTree` or
+/// `Arc<ConcurrentTree>` without an external `Mutex`.
+///
+/// The current version is a `PersistentTree` behind a `RwLock`. Writers are
+/// serialized by a separate lock and build the next version outside of the
+/// `RwLock`, which they only hold to swap the root: readers never wait for a
+/// modification to be computed, and `snapshot` gives them a consistent view
+/// that later writes do not affect.
+#[derive(Debug)]
+pub struct ConcurrentTree<T, C = Natural> {
+    current: RwLock<PersistentTree<T, C>>,
+    writer: Mutex<()>,
+}
+
+impl<T> ConcurrentTree<T> {
+    /// Returns an empty tree, ordered by the natural order of `T`.
+    pub fn new() -> Self {
+        Self::with_comparator()
+    }
+}
+
+impl<T, C> ConcurrentTree<T, C> {
+    /// Returns an empty tree, ordered by the comparator `C`.
+    pub fn with_comparator() -> Self {
+        ConcurrentTree {
+            current: RwLock::new(PersistentTree::with_comparator()),
+            writer: Mutex::new(()),
+        }
+    }
+
+    /// Returns the current version of the tree, in O(1).
+    pub fn snapshot(&self) -> PersistentTree<T, C> {
+        // Versions are only swapped once complete: a panicking writer can not
+        // leave a partially modified tree behind the lock
+        self.current
+            .read()
+            .unwrap_or_else(PoisonError::into_inner)
+            .clone()
+    }
+
+    /// Returns the number of values of the current version.
+    pub fn len(&self) -> usize {
+        self.snapshot().len()
+    }
+
+    /// Returns true iff the current version holds no value.
+    pub fn is_empty(&self) -> bool {
+        self.snapshot().is_empty()
+    }
+}
+
+/// Reads of the current version, available whether or not `T` is `Clone`.
+impl<T, C> ConcurrentTree<T, C>
+where
+    C: Compare<T>,
+{
+    /// Returns true iff `value` belongs to the current version of the tree.
+    pub fn contains<Q>(&self, target: &Q) -> bool
+    where
+        T: Borrow<Q>,
+        C: Compare<Q>,
+        Q: ?Sized,
+    {
+        self.current
+            .read()
+            .unwrap_or_else(PoisonError::into_inner)
+            .contains(target)
+    }
+}
+
+impl<T, C> ConcurrentTree<T, C>
+where
+    T: Clone,
+    C: Compare<T>,
+{
+    /// Inserts `value` into the tree.
+    /// Returns `TreeOpError::ValueAlreadyExists` with `value` iff an equal
+    /// value was already contained in the tree.
+    pub fn insert(&self, value: T) -> Result<(), TreeOpError<T>> {
+        self.update(|tree| tree.insert(value))
+    }
+
+    /// Deletes `value` from the tree.
+    /// When the value is not found the tree, `TreeOpError::NoValue` is returned.
+    pub fn delete<Q>(&self, target: &Q) -> Result<(), TreeOpError>
+    where
+        T: Borrow<Q>,
+        C: Compare<Q>,
+        Q: ?Sized,
+    {
+        self.update(|tree| tree.delete(target))
+    }
+
+    /// Builds the next version from the current one with `modify` and makes
+    /// it current, unless `modify` failed.
+    fn update<F, E>(&self, modify: F) -> Result<(), E>
+    where
+        F: FnOnce(&PersistentTree<T, C>) -> Result<PersistentTree<T, C>, E>,
+    {
+        let _writer = self.writer.lock().unwrap_or_else(PoisonError::into_inner);
+        let next = modify(&self.snapshot())?;
+        // The old version is dropped once the lock is released, by the last
+        // reader holding it
+        let _old = std::mem::replace(
+            &mut *self.current.write().unwrap_or_else(PoisonError::into_inner),
+            next,
+        );
+        Ok(())
+    }
+}
+
+impl<T, C> Default for ConcurrentTree<T, C> {
+    fn default() -> Self {
+        Self::with_comparator()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::thread;
+
+    const WRITERS: u32 = 4;
+    const READERS: usize = 8;
+    const VALUES: u32 = 2_000;
+
+    #[test]
+    fn inserts_and_deletes() {
+        let tree = ConcurrentTree::new();
+        tree.insert(2).expect("Failed to insert");
+        tree.insert(2).expect_err("Value was already in the tree");
+        assert!(tree.contains(&2));
+
+        let before = tree.snapshot();
+        tree.delete(&2).expect("Failed to delete");
+        tree.delete(&2).expect_err("Value is not in the tree");
+        assert!(!tree.contains(&2) && tree.is_empty());
+        // Snapshots are not affected by later writes
+        assert!(before.contains(&2));
+    }
+
+    #[test]
+    fn stresses_readers_and_writers() {
+        let tree = ConcurrentTree::new();
+
+        thread::scope(|s| {
+            // Each writer inserts its own residue class, then deletes the
+            // multiples of 3 among them
+            for w in 0..WRITERS {
+                let tree = &tree;
+                s.spawn(move || {
+                    let values = (0..VALUES).filter(|v| v % WRITERS == w);
+                    for v in values.clone() {
+                        tree.insert(v).expect("Failed to insert");
+                    }
+                    for v in values.filter(|v| v % 3 == 0) {
+                        tree.delete(&v).expect("Failed to delete");
+                    }
+                });
+            }
+
+            for _ in 0..READERS {
+                let tree = &tree;
+                s.spawn(move || {
+                    for i in 0..VALUES {
+                        let _ = tree.contains(&i);
+                        // Every snapshot is a consistent, ordered version
+                        let snapshot = tree.snapshot();
+                        let values: Vec<_> = snapshot.iter().collect();
+                        assert!(values.windows(2).all(|w| w[0] < w[1]));
+                        assert_eq!(values.len(), snapshot.len());
+                    }
+                });
+            }
+        });
+
+        let expected = (0..VALUES).filter(|v| v % 3 != 0);
+        assert!(tree.snapshot().iter().copied().eq(expected));
+    }
+
+    #[test]
+    fn looks_up_unclonable_values() {
+        #[derive(PartialEq, Eq, PartialOrd, Ord)]
+        struct Key(u32);
+        let tree = ConcurrentTree::<Key>::new();
+        assert!(!tree.contains(&Key(1)) && tree.is_empty());
+    }
+
+    #[test]
+    fn rejects_concurrent_duplicates() {
+        let tree = ConcurrentTree::new();
+        let inserted: usize = thread::scope(|s| {
+            let handles: Vec<_> = (0..READERS)
+                .map(|_| {
+                    let tree = &tree;
+                    s.spawn(move || (0..VALUES).filter(|v| tree.insert(*v).is_ok()).count())
+                })
+                .collect();
+            handles.into_iter().map(|h| h.join().unwrap()).sum()
+        });
+
+        // Each value was inserted by exactly one thread
+        assert_eq!(inserted, VALUES as usize);
+        assert_eq!(tree.len(), VALUES as usize);
+    }
+}
@@ -0,0 +1,395 @@
+use crate::{Compare, Natural, Node, Tree};
+use std::cmp::Ordering;
+
+/// Cursor over a `Tree`, moving between its nodes and editing them in place.
+///
+/// Created by `Tree::cursor_mut`, at the root. Nodes have no parent links:
+/// the nodes on the path from the root are detached while the cursor is
+/// below them, each missing the child on the side it was left through, and
+/// put back when the cursor moves up or is dropped. Moving to a parent or a
+/// child is O(1). `move_next` and `move_prev` are O(height) per move, but
+/// amortized O(1) over a full walk: an in-order walk is O(n).
+///
+/// Like the cursors of `std::collections::LinkedList`, the cursor may point
+/// to a "ghost" position off the tree, between the largest and the smallest
+/// values: `move_next` goes there from the largest value, and from there to
+/// the smallest one.
+pub struct CursorMut<'a, T, C = Natural> {
+    tree: &'a mut Tree<T, C>,
+    /// Detached ancestors of the current node, from the root, with the side
+    /// of the missing child.
+    path: Vec<(Box<Node<T, C>>, Ordering)>,
+    /// Sub-tree whose root is the current node, or the whole tree when at
+    /// the ghost position.
+    current: Tree<T, C>,
+    ghost: bool,
+}
+
+impl<'a, T, C> CursorMut<'a, T, C> {
+    pub(crate) fn new(tree: &'a mut Tree<T, C>) -> Self {
+        let current = Tree(tree.0.take());
+        CursorMut {
+            tree,
+            path: Vec::new(),
+            ghost: current.0.is_none(),
+            current,
+        }
+    }
+
+    /// Returns the current value, or `None` at the ghost position.
+    pub fn current(&self) -> Option<&T> {
+        match self.current.0 {
+            Some(ref n) if !self.ghost => Some(&n.value),
+            _ => None,
+        }
+    }
+
+    /// Returns the number of occurrences of the current value, see
+    /// `Tree::count`, or 0 at the ghost position.
+    pub fn count(&self) -> usize {
+        match self.current.0 {
+            Some(ref n) if !self.ghost => n.count,
+            _ => 0,
+        }
+    }
+
+    /// Moves to the parent of the current node.
+    /// Returns false, without moving, at the root or the ghost position.
+    pub fn move_parent(&mut self) -> bool {
+        let (mut parent, side) = match self.path.pop() {
+            Some(entry) => entry,
+            None => return false,
+        };
+        let child = Tree(self.current.0.take());
+        if side == Ordering::Less {
+            parent.left = child;
+        } else {
+            parent.right = child;
+        }
+        self.current = Tree(Some(parent));
+        true
+    }
+
+    /// Moves to the left child of the current node.
+    /// Returns false, without moving, if there is none.
+    pub fn move_left(&mut self) -> bool {
+        self.move_child(Ordering::Less)
+    }
+
+    /// Moves to the right child of the current node.
+    /// Returns false, without moving, if there is none.
+    pub fn move_right(&mut self) -> bool {
+        self.move_child(Ordering::Greater)
+    }
+
+    fn move_child(&mut self, side: Ordering) -> bool {
+        if self.ghost {
+            return false;
+        }
+        let n = match self.current.0 {
+            Some(ref mut n) => n,
+            None => return false,
+        };
+        let child = if side == Ordering::Less {
+            &mut n.left
+        } else {
+            &mut n.right
+        };
+        if child.0.is_none() {
+            return false;
+        }
+
+        let child = Tree(child.0.take());
+        let parent = std::mem::replace(&mut self.current, child)
+            .0
+            .take()
+            .unwrap();
+        self.path.push((parent, side));
+        true
+    }
+
+    /// Moves to the next value in increasing order, to the ghost position
+    /// from the largest value, and to the smallest value from the ghost
+    /// position.
+    pub fn move_next(&mut self) {
+        self.move_in_order(Ordering::Greater);
+    }
+
+    /// Moves to the previous value in increasing order, to the ghost
+    /// position from the smallest value, and to the largest value from the
+    /// ghost position.
+    pub fn move_prev(&mut self) {
+        self.move_in_order(Ordering::Less);
+    }
+
+    /// Moves to the in-order neighbour on `side`.
+    fn move_in_order(&mut self, side: Ordering) {
+        let back = side.reverse();
+        if self.ghost {
+            // The whole tree is current: go to its extreme value on `back`
+            self.ghost = self.current.0.is_none();
+            while self.move_child(back) {}
+        } else if self.move_child(side) {
+            while self.move_child(back) {}
+        } else {
+            self.climb_to(side);
+        }
+    }
+
+    /// Moves up to the closest ancestor the current sub-tree is on the `back`
+    /// side of, i.e. the in-order neighbour on `side` of the whole sub-tree,
+    /// or to the ghost position if there is none.
+    fn climb_to(&mut self, side: Ordering) {
+        loop {
+            let from = match self.path.last() {
+                Some(&(_, from)) => from,
+                None => {
+                    self.ghost = true;
+                    return;
+                }
+            };
+            self.move_parent();
+            if from != side {
+                return;
+            }
+        }
+    }
+
+    /// Moves to the root of the tree, leaving the ghost position.
+    pub fn move_root(&mut self) {
+        while self.move_parent() {}
+        self.ghost = self.current.0.is_none();
+    }
+}
+
+impl<'a, T, C> CursorMut<'a, T, C>
+where
+    C: Compare<T>,
+{
+    /// Removes the current node and returns its value, whatever its number of
+    /// occurrences, then moves to the next value, like `move_next`.
+    /// Returns `None`, without moving, at the ghost position.
+    pub fn remove_current(&mut self) -> Option<T> {
+        if self.ghost {
+            return None;
+        }
+        let n = self.current.0.as_mut()?;
+
+        match n.left.take_max() {
+            // Like `Tree::delete`, the node takes the value of its in-order
+            // predecessor, and the next value is the one of the current node
+            Some(predecessor) => {
+                let value = std::mem::replace(&mut n.value, predecessor.value);
+                n.count = predecessor.count;
+                self.move_next();
+                Some(value)
+            }
+            // The right sub-tree takes the place of the node: the next value
+            // is its smallest one, or above it if it is empty
+            None => {
+                let mut n = self.current.0.take().unwrap();
+                self.current = Tree(n.right.0.take());
+                if self.current.0.is_some() {
+                    while self.move_left() {}
+                } else {
+                    self.climb_to(Ordering::Greater);
+                }
+                Some(n.value)
+            }
+        }
+    }
+
+    /// Replaces the current value by `value` and returns it, keeping its
+    /// number of occurrences. `value` must lie strictly between the previous
+    /// and the next value of the tree, so that it stays ordered: otherwise,
+    /// or at the ghost position, `value` is given back.
+    pub fn replace_current(&mut self, value: T) -> Result<T, T> {
+        if self.ghost {
+            return Err(value);
+        }
+        let n = match self.current.0 {
+            Some(ref n) => n,
+            None => return Err(value),
+        };
+
+        let fits = self
+            .neighbour(n, Ordering::Less)
+            .map_or(true, |prev| C::compare(prev, &value) == Ordering::Less)
+            && self
+                .neighbour(n, Ordering::Greater)
+                .map_or(true, |next| C::compare(&value, next) == Ordering::Less);
+        if !fits {
+            return Err(value);
+        }
+        let n = self.current.0.as_mut().unwrap();
+        Ok(std::mem::replace(&mut n.value, value))
+    }
+
+    /// Returns the in-order neighbour on `side` of the current node `n`.
+    fn neighbour<'b>(&'b self, n: &'b Node<T, C>, side: Ordering) -> Option<&'b T> {
+        let child = |n: &'b Node<T, C>, side| {
+            if side == Ordering::Less {
+                &n.left
+            } else {
+                &n.right
+            }
+        };
+
+        // Extreme value of the child on `side`, or closest ancestor the node
+        // is on the other side of
+        match child(n, side).0 {
+            Some(ref c) => {
+                let mut c = c;
+                while let Some(ref next) = child(c, side.reverse()).0 {
+                    c = next;
+                }
+                Some(&c.value)
+            }
+            None => self
+                .path
+                .iter()
+                .rev()
+                .find(|(_, from)| *from != side)
+                .map(|(p, _)| &p.value),
+        }
+    }
+}
+
+impl<'a, T, C> Drop for CursorMut<'a, T, C> {
+    /// Puts the detached nodes back into the tree.
+    fn drop(&mut self) {
+        while self.move_parent() {}
+        self.tree.0 = self.current.0.take();
+    }
+}
+
+impl<T, C> Tree<T, C> {
+    /// Returns a cursor at the root of the tree, or at the ghost position if
+    /// the tree is empty.
+    pub fn cursor_mut(&mut self) -> CursorMut<'_, T, C> {
+        CursorMut::new(self)
+    }
+}
+
+impl<T, C> Tree<T, C>
+where
+    C: Compare<T>,
+{
+    /// Only keeps the values for which `f` returns true, visiting them in
+    /// increasing order.
+    pub fn retain<F>(&mut self, mut f: F)
+    where
+        F: FnMut(&T) -> bool,
+    {
+        let mut cursor = self.cursor_mut();
+        while cursor.move_left() {}
+        while let Some(value) = cursor.current() {
+            if f(value) {
+                cursor.move_next();
+            } else {
+                cursor.remove_current();
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::test::tree;
+
+    #[test]
+    fn moves() {
+        // 15 -> (10 -> 8, 12), (20 -> 18, 30)
+        let mut bst = tree(&[15, 10, 20, 8, 12, 18, 30]);
+        let mut cursor = bst.cursor_mut();
+        assert_eq!(cursor.current(), Some(&15));
+        assert!(!cursor.move_parent());
+        assert!(cursor.move_left() && cursor.move_right());
+        assert_eq!(cursor.current(), Some(&12));
+        assert!(!cursor.move_left() && !cursor.move_right());
+        assert!(cursor.move_parent());
+        assert_eq!(cursor.current(), Some(&10));
+
+        cursor.move_root();
+        assert!(cursor.move_left() && cursor.move_left());
+        let mut values = Vec::new();
+        while let Some(v) = cursor.current() {
+            values.push(*v);
+            cursor.move_next();
+        }
+        assert_eq!(values, [8, 10, 12, 15, 18, 20, 30]);
+
+        // From the ghost position, around the tree backwards
+        values.clear();
+        cursor.move_prev();
+        while let Some(v) = cursor.current() {
+            values.push(*v);
+            cursor.move_prev();
+        }
+        assert_eq!(values, [30, 20, 18, 15, 12, 10, 8]);
+        cursor.move_next();
+        assert_eq!(cursor.current(), Some(&8));
+        drop(cursor);
+
+        assert_eq!(bst, tree(&[15, 10, 20, 8, 12, 18, 30]));
+        assert_eq!(Tree::<i32>::new().cursor_mut().current(), None);
+    }
+
+    #[test]
+    fn removes_and_replaces() {
+        let mut bst = tree(&[15, 10, 20, 8, 12, 11, 18, 30]);
+        let mut cursor = bst.cursor_mut();
+        // A node with a left child takes its predecessor
+        assert_eq!(cursor.remove_current(), Some(15));
+        assert_eq!(cursor.current(), Some(&18));
+        cursor.move_prev();
+        assert_eq!(cursor.current(), Some(&12));
+
+        assert_eq!(cursor.replace_current(13), Ok(12));
+        assert_eq!(cursor.replace_current(18), Err(18));
+        assert_eq!(cursor.replace_current(11), Err(11));
+        cursor.move_prev();
+        // A leaf: the next value is an ancestor
+        assert_eq!(cursor.remove_current(), Some(11));
+        assert_eq!(cursor.current(), Some(&13));
+
+        cursor.move_root();
+        assert!(cursor.move_right() && cursor.move_right());
+        assert_eq!(cursor.remove_current(), Some(30));
+        assert_eq!(cursor.current(), None);
+        assert_eq!(cursor.remove_current(), None);
+        drop(cursor);
+
+        assert!(bst.iter().copied().eq([8, 10, 13, 18, 20]));
+        assert_eq!(bst.validate(), Ok(()));
+    }
+
+    #[test]
+    fn retains() {
+        let mut bst: Tree<i32> = (0..100).collect();
+        bst.checked(|t| t.retain(|v| v % 3 != 0));
+        assert!(bst.iter().copied().eq((0..100).filter(|v| v % 3 != 0)));
+
+        let mut visited = Vec::new();
+        bst.checked(|t| {
+            t.retain(|v| {
+                visited.push(*v);
+                *v > 50
+            })
+        });
+        assert!(visited.into_iter().eq((0..100).filter(|v| v % 3 != 0)));
+        assert!(bst.iter().copied().eq((51..100).filter(|v| v % 3 != 0)));
+
+        bst.checked(|t| t.retain(|_| false));
+        assert!(bst.is_empty());
+    }
+
+    #[test]
+    fn retains_in_degenerate_trees() {
+        let mut bst = crate::test::sorted_chain(1_000_000);
+        bst.checked(|t| t.retain(|v| v % 2 == 0));
+        assert_eq!(bst.len(), 500_000);
+        assert_eq!(bst.height(), 500_000);
+    }
+}
@@ -0,0 +1,362 @@
+use crate::{Compare, Natural, Node, Tree};
+use std::borrow::Borrow;
+use std::cmp::Ordering;
+use std::collections::VecDeque;
+use std::ops::{Bound, RangeBounds};
+
+/// In-order iterator over the values of a `Tree`, in increasing order.
+///
+/// Created by `Tree::iter`. The path from the root to the next value is kept
+/// on an explicit stack instead of the call stack, so degenerate trees are
+/// traversed without recursion. Each value is yielded once, whatever its
+/// multiplicity (see `Tree::count`).
+pub struct Iter<'a, T, C = Natural> {
+    stack: Vec<&'a Node<T, C>>,
+}
+
+impl<'a, T, C> Iter<'a, T, C> {
+    fn new(tree: &'a Tree<T, C>) -> Self {
+        let mut iter = Iter { stack: Vec::new() };
+        iter.push_left_spine(tree);
+        iter
+    }
+
+    /// Pushes `tree` and all its left descendants.
+    fn push_left_spine(&mut self, mut tree: &'a Tree<T, C>) {
+        while let Some(ref n) = tree.0 {
+            self.stack.push(n);
+            tree = &n.left;
+        }
+    }
+}
+
+impl<'a, T, C> Iterator for Iter<'a, T, C> {
+    type Item = &'a T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let n = self.stack.pop()?;
+        self.push_left_spine(&n.right);
+        Some(&n.value)
+    }
+}
+
+/// In-order iterator over the values of a `Tree` within a range.
+///
+/// Created by `Tree::range`. Only the nodes on the paths to the bounds of the
+/// range and the values in the range are visited.
+pub struct Range<'a, T, C = Natural> {
+    iter: Iter<'a, T, C>,
+    /// Largest value of the range, `None` once it has been yielded.
+    last: Option<&'a T>,
+}
+
+impl<'a, T, C> Range<'a, T, C>
+where
+    C: Compare<T>,
+{
+    fn new<Q>(tree: &'a Tree<T, C>, start: Bound<&Q>, end: Bound<&Q>) -> Self
+    where
+        T: Borrow<Q>,
+        C: Compare<Q>,
+        Q: ?Sized,
+    {
+        let mut iter = Iter { stack: Vec::new() };
+
+        // Push the nodes greater than `start` on the path towards it, they are
+        // exactly the nodes whose left sub-tree was skipped
+        let mut current = tree;
+        while let Some(ref n) = current.0 {
+            let above_start = match start {
+                Bound::Included(start) => C::compare(start, n.value.borrow()) != Ordering::Greater,
+                Bound::Excluded(start) => C::compare(start, n.value.borrow()) == Ordering::Less,
+                Bound::Unbounded => true,
+            };
+            current = if above_start {
+                iter.stack.push(n);
+                &n.left
+            } else {
+                &n.right
+            };
+        }
+
+        let last = match end {
+            Bound::Included(end) => tree.floor(end),
+            Bound::Excluded(end) => tree.predecessor(end),
+            Bound::Unbounded => tree.max(),
+        };
+
+        Range { iter, last }
+    }
+}
+
+impl<'a, T, C> Iterator for Range<'a, T, C>
+where
+    C: Compare<T>,
+{
+    type Item = &'a T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let last = self.last?;
+        let value = self.iter.next()?;
+        let ordering = C::compare(value, last);
+        if ordering != Ordering::Less {
+            self.last = None;
+        }
+
+        // Empty ranges start past their last value
+        if ordering != Ordering::Greater {
+            Some(value)
+        } else {
+            None
+        }
+    }
+}
+
+/// Owning in-order iterator over the values of a `Tree`.
+///
+/// Created by `Tree::into_iter`. Nodes are freed as soon as their value has
+/// been yielded.
+pub struct IntoIter<T, C = Natural> {
+    stack: Vec<Box<Node<T, C>>>,
+}
+
+impl<T, C> IntoIter<T, C> {
+    fn push_left_spine(&mut self, mut tree: Tree<T, C>) {
+        while let Some(mut n) = tree.0.take() {
+            tree = Tree(n.left.0.take());
+            self.stack.push(n);
+        }
+    }
+}
+
+impl<T, C> Iterator for IntoIter<T, C> {
+    type Item = T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let mut n = self.stack.pop()?;
+        self.push_left_spine(Tree(n.right.0.take()));
+        Some(n.value)
+    }
+}
+
+impl<T, C> Drop for IntoIter<T, C> {
+    /// Drains the remaining nodes one by one, so that dropping a partially
+    /// consumed iterator does not recurse through the remaining sub-trees.
+    fn drop(&mut self) {
+        for _ in self {}
+    }
+}
+
+/// Pre-order iterator: each value is yielded before its left and right
+/// sub-trees. Created by `Tree::pre_order`.
+pub struct PreOrder<'a, T, C = Natural> {
+    stack: Vec<&'a Node<T, C>>,
+}
+
+impl<'a, T, C> Iterator for PreOrder<'a, T, C> {
+    type Item = &'a T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let n = self.stack.pop()?;
+        // Push right first so that the left sub-tree is visited first
+        if let Some(ref right) = n.right.0 {
+            self.stack.push(right);
+        }
+        if let Some(ref left) = n.left.0 {
+            self.stack.push(left);
+        }
+        Some(&n.value)
+    }
+}
+
+/// Post-order iterator: each value is yielded after its left and right
+/// sub-trees. Created by `Tree::post_order`.
+pub struct PostOrder<'a, T, C = Natural> {
+    /// Nodes still to visit, with whether their children were already pushed.
+    stack: Vec<(&'a Node<T, C>, bool)>,
+}
+
+impl<'a, T, C> Iterator for PostOrder<'a, T, C> {
+    type Item = &'a T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let (n, expanded) = self.stack.pop()?;
+            if expanded {
+                return Some(&n.value);
+            }
+
+            self.stack.push((n, true));
+            if let Some(ref right) = n.right.0 {
+                self.stack.push((right, false));
+            }
+            if let Some(ref left) = n.left.0 {
+                self.stack.push((left, false));
+            }
+        }
+    }
+}
+
+/// Breadth-first iterator: values are yielded level by level, from left to
+/// right. Created by `Tree::level_order`.
+pub struct LevelOrder<'a, T, C = Natural> {
+    queue: VecDeque<&'a Node<T, C>>,
+}
+
+impl<'a, T, C> Iterator for LevelOrder<'a, T, C> {
+    type Item = &'a T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let n = self.queue.pop_front()?;
+        if let Some(ref left) = n.left.0 {
+            self.queue.push_back(left);
+        }
+        if let Some(ref right) = n.right.0 {
+            self.queue.push_back(right);
+        }
+        Some(&n.value)
+    }
+}
+
+/// Traversals of a `Tree`.
+///
+/// There is no `iter_mut`: mutating values in place could break the ordering
+/// of the tree.
+impl<T, C> Tree<T, C> {
+    /// Returns an iterator over the values of the tree, in increasing order.
+    pub fn iter(&self) -> Iter<'_, T, C> {
+        Iter::new(self)
+    }
+
+    /// Returns an iterator over the values of the tree contained in `range`,
+    /// in increasing order. The bounds may be any borrowed form of the values.
+    pub fn range<Q, R>(&self, range: R) -> Range<'_, T, C>
+    where
+        T: Borrow<Q>,
+        C: Compare<T> + Compare<Q>,
+        Q: ?Sized,
+        R: RangeBounds<Q>,
+    {
+        Range::new(self, range.start_bound(), range.end_bound())
+    }
+
+    /// Returns an iterator over the values of the tree in pre-order.
+    pub fn pre_order(&self) -> PreOrder<'_, T, C> {
+        PreOrder {
+            stack: self.0.as_deref().into_iter().collect(),
+        }
+    }
+
+    /// Returns an iterator over the values of the tree in post-order.
+    pub fn post_order(&self) -> PostOrder<'_, T, C> {
+        PostOrder {
+            stack: self.0.as_deref().map(|n| (n, false)).into_iter().collect(),
+        }
+    }
+
+    /// Returns an iterator over the values of the tree in breadth-first order.
+    pub fn level_order(&self) -> LevelOrder<'_, T, C> {
+        LevelOrder {
+            queue: self.0.as_deref().into_iter().collect(),
+        }
+    }
+}
+
+impl<T, C> IntoIterator for Tree<T, C> {
+    type Item = T;
+    type IntoIter = IntoIter<T, C>;
+
+    /// Consumes the tree, returning its values in increasing order.
+    fn into_iter(self) -> Self::IntoIter {
+        let mut iter = IntoIter { stack: Vec::new() };
+        iter.push_left_spine(self);
+        iter
+    }
+}
+
+impl<'a, T, C> IntoIterator for &'a Tree<T, C> {
+    type Item = &'a T;
+    type IntoIter = Iter<'a, T, C>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::test::tree;
+
+    #[test]
+    fn iterates_in_order() {
+        let bst = tree(&[15, 10, 20, 8, 12, 18, 30]);
+        let values: Vec<_> = bst.iter().copied().collect();
+        assert_eq!(values, vec![8, 10, 12, 15, 18, 20, 30]);
+        assert_eq!((&bst).into_iter().count(), 7);
+        assert_eq!(bst.into_iter().collect::<Vec<_>>(), values);
+        assert_eq!(Tree::<i32>::new().iter().next(), None);
+    }
+
+    #[test]
+    fn iterates_over_ranges() {
+        let bst = tree(&[15, 10, 20, 8, 12, 18, 30]);
+        let range = |r: (Bound<i32>, Bound<i32>)| bst.range(r).copied().collect::<Vec<_>>();
+
+        assert_eq!(
+            range((Bound::Included(10), Bound::Excluded(20))),
+            vec![10, 12, 15, 18]
+        );
+        assert_eq!(
+            range((Bound::Excluded(12), Bound::Excluded(20))),
+            vec![15, 18]
+        );
+        assert_eq!(
+            bst.range(9..=20).copied().collect::<Vec<_>>(),
+            vec![10, 12, 15, 18, 20]
+        );
+        assert_eq!(
+            bst.range(16..).copied().collect::<Vec<_>>(),
+            vec![18, 20, 30]
+        );
+        assert_eq!(bst.range(..12).copied().collect::<Vec<_>>(), vec![8, 10]);
+        assert_eq!(bst.range(..).count(), 7);
+        assert_eq!(bst.range(31..).next(), None);
+        assert_eq!(bst.range(13..15).next(), None);
+        assert_eq!(range((Bound::Included(20), Bound::Excluded(10))), vec![]);
+    }
+
+    #[test]
+    fn iterates_in_pre_order() {
+        let values: Vec<_> = tree(&[15, 10, 20, 8, 12, 18, 30])
+            .pre_order()
+            .copied()
+            .collect();
+        assert_eq!(values, vec![15, 10, 8, 12, 20, 18, 30]);
+    }
+
+    #[test]
+    fn iterates_in_post_order() {
+        let values: Vec<_> = tree(&[15, 10, 20, 8, 12, 18, 30])
+            .post_order()
+            .copied()
+            .collect();
+        assert_eq!(values, vec![8, 12, 10, 18, 30, 20, 15]);
+    }
+
+    #[test]
+    fn iterates_in_level_order() {
+        let values: Vec<_> = tree(&[15, 10, 20, 8, 12, 18, 30])
+            .level_order()
+            .copied()
+            .collect();
+        assert_eq!(values, vec![15, 10, 20, 8, 12, 18, 30]);
+    }
+
+    #[test]
+    fn drops_partially_consumed() {
+        let mut iter = tree(&[15, 10, 20, 8, 12, 18, 30]).into_iter();
+        assert_eq!(iter.next(), Some(8));
+        assert_eq!(iter.next(), Some(10));
+    }
+}
@@ -1,102 +1,295 @@
+use std::borrow::Borrow;
 use std::cmp::Ordering;
-use std::fmt::Debug;
+use std::error::Error;
+use std::fmt;
+use std::marker::PhantomData;
+
+pub mod arena;
+mod avl;
+mod build;
+mod check;
+mod compare;
+mod concurrent;
+mod cursor;
+mod iter;
+mod load;
+pub mod map;
+pub mod persistent;
+mod query;
+mod render;
+mod serial;
+mod set;
+
+pub use arena::ArenaTree;
+pub use avl::AvlTree;
+pub use check::{Stats, Violation};
+pub use compare::{Compare, Natural, Reverse};
+pub use concurrent::ConcurrentTree;
+pub use cursor::CursorMut;
+pub use iter::{IntoIter, Iter, LevelOrder, PostOrder, PreOrder, Range};
+pub use load::{read_values, LoadError};
+pub use map::TreeMap;
+pub use persistent::PersistentTree;
+pub use serial::{DecodeError, Encode};
 
 /// Simple binary search tree.
 ///
 /// For every node of value `v`, all elements in the left sub-tree are smaller
-/// than `v` and all elements in the right sub-tree are larger than `v`.
+/// than `v` and all elements in the right sub-tree are larger than `v`, as
+/// ordered by the comparator `C` (see `Compare`).
 #[derive(Debug)]
-pub struct Tree<T>(Option<Box<Node<T>>>);
+pub struct Tree<T, C = Natural>(Option<Box<Node<T, C>>>);
 
-/// Internal Node representation with a `value` and the left and right sub-trees.
+/// Internal Node representation with a `value`, its multiplicity `count` and
+/// the left and right sub-trees.
 #[derive(Debug)]
-struct Node<T> {
+struct Node<T, C> {
     value: T,
-    left: Tree<T>,
-    right: Tree<T>,
+    /// Number of occurrences of `value`, only above 1 for `Duplicates::Count`.
+    count: usize,
+    left: Tree<T, C>,
+    right: Tree<T, C>,
+    order: PhantomData<C>,
 }
 
 /// Errors thrown by operations on a Tree.
-#[derive(Debug)]
-pub enum TreeOpError {
+///
+/// Insertions give the rejected value back in `ValueAlreadyExists`, so that
+/// it is neither lost nor cloned beforehand. Errors holding a `Debug` value
+/// implement `std::error::Error`, and can be propagated with `?` into a
+/// `Box<dyn Error>`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum TreeOpError<V = ()> {
+    /// The value to delete is not in the tree.
     NoValue,
-    ValueAlreadyExists,
+    /// An equal value is already in the tree, `V` being the rejected one.
+    ValueAlreadyExists(V),
+    /// The tree to add is empty.
     NoneTree,
 }
 
-impl<T> Tree<T>
-where
-    T: Ord + Debug,
-{
-    /// Returns an empty tree.
+impl<V> TreeOpError<V> {
+    /// Returns the rejected value, if any.
+    pub fn into_value(self) -> Option<V> {
+        match self {
+            TreeOpError::ValueAlreadyExists(value) => Some(value),
+            TreeOpError::NoValue | TreeOpError::NoneTree => None,
+        }
+    }
+}
+
+impl<V: fmt::Debug> fmt::Display for TreeOpError<V> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TreeOpError::NoValue => write!(f, "value not found in the tree"),
+            TreeOpError::ValueAlreadyExists(value) => {
+                write!(f, "value {:?} already exists in the tree", value)
+            }
+            TreeOpError::NoneTree => write!(f, "cannot add an empty tree"),
+        }
+    }
+}
+
+impl<V: fmt::Debug> Error for TreeOpError<V> {}
+
+/// Policy of `Tree::insert_with` for values already contained in the tree.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Duplicates {
+    /// Fail with `TreeOpError::ValueAlreadyExists`, like `Tree::insert`.
+    Reject,
+    /// Replace the stored value by the inserted one.
+    Replace,
+    /// Use the tree as a multiset: count the occurrences of the value.
+    Count,
+}
+
+impl<T> Tree<T> {
+    /// Returns an empty tree, ordered by the natural order of `T`.
     pub fn new() -> Self {
         Tree(None)
     }
+}
+
+impl<T, C> Tree<T, C> {
+    /// Returns an empty tree, ordered by the comparator `C`.
+    pub fn with_comparator() -> Self {
+        Tree(None)
+    }
 
     /// Returns a tree containing a single value.
     fn leaf(value: T) -> Self {
         Tree(Some(Box::new(Node {
             value,
+            count: 1,
             left: Tree(None),
             right: Tree(None),
+            order: PhantomData,
         })))
     }
+}
+
+/// Lookups accept any borrowed form `Q` of the values, like `BTreeSet`: a
+/// `Tree<String>` can be searched with a `&str`.
+impl<T, C> Tree<T, C>
+where
+    C: Compare<T>,
+{
+    /// Returns the sub-tree whose root holds `target`, or the empty sub-tree
+    /// where `target` would be inserted.
+    ///
+    /// Walks down with a cursor on the current `Option<Box<Node<T>>>` instead
+    /// of recursing, so that degenerate trees can not overflow the stack.
+    fn find_slot<Q>(&mut self, target: &Q) -> &mut Self
+    where
+        T: Borrow<Q>,
+        C: Compare<Q>,
+        Q: ?Sized,
+    {
+        let mut slot = self;
+        loop {
+            let ordering = match slot.0 {
+                Some(ref n) => C::compare(target, n.value.borrow()),
+                None => return slot,
+            };
+            if ordering == Ordering::Equal {
+                return slot;
+            }
+
+            // Looked up again to end the shared borrow before moving down
+            let n = slot.0.as_mut().unwrap();
+            slot = if ordering == Ordering::Less {
+                &mut n.left
+            } else {
+                &mut n.right
+            };
+        }
+    }
 
     /// Inserts `value` into the tree.
-    /// Returns `TreeOpError::ValueAlreadyExists` iff the `value` was already
-    /// contained in the tree.
-    pub fn insert(&mut self, value: T) -> Result<(), TreeOpError> {
-        match self.0 {
-            Some(ref mut n) => match value.cmp(&n.value) {
-                Ordering::Equal => Err(TreeOpError::ValueAlreadyExists),
-                Ordering::Less => n.left.insert(value),
-                Ordering::Greater => n.right.insert(value),
-            },
-            None => {
-                *self = Tree::leaf(value);
-                Ok(())
+    /// Returns `TreeOpError::ValueAlreadyExists` with `value` iff an equal
+    /// value was already contained in the tree.
+    pub fn insert(&mut self, value: T) -> Result<(), TreeOpError<T>> {
+        self.insert_with(value, Duplicates::Reject).map(|_| ())
+    }
+
+    /// Inserts `value` into the tree, handling an equal value already
+    /// contained in the tree according to `duplicates`.
+    /// Returns the replaced value with `Duplicates::Replace`, and
+    /// `TreeOpError::ValueAlreadyExists` with `Duplicates::Reject`.
+    pub fn insert_with(
+        &mut self,
+        value: T,
+        duplicates: Duplicates,
+    ) -> Result<Option<T>, TreeOpError<T>> {
+        let slot = self.find_slot::<T>(&value);
+        match (slot.0.as_mut(), duplicates) {
+            (None, _) => {
+                *slot = Tree::leaf(value);
+                Ok(None)
+            }
+            (Some(_), Duplicates::Reject) => Err(TreeOpError::ValueAlreadyExists(value)),
+            (Some(n), Duplicates::Replace) => Ok(Some(std::mem::replace(&mut n.value, value))),
+            (Some(n), Duplicates::Count) => {
+                n.count += 1;
+                Ok(None)
             }
         }
     }
 
+    /// Returns the number of occurrences of `target` in the tree: 0 or 1
+    /// unless values were inserted with `Duplicates::Count`.
+    pub fn count<Q>(&self, target: &Q) -> usize
+    where
+        T: Borrow<Q>,
+        C: Compare<Q>,
+        Q: ?Sized,
+    {
+        let mut current = self;
+        while let Some(ref n) = current.0 {
+            current = match C::compare(target, n.value.borrow()) {
+                Ordering::Equal => return n.count,
+                Ordering::Less => &n.left,
+                Ordering::Greater => &n.right,
+            };
+        }
+
+        0
+    }
+
     /// Returns true iff `value` belongs to the tree.
-    pub fn contains(&self, target: T) -> bool {
-        match self.0 {
-            Some(ref n) => match target.cmp(&n.value) {
-                Ordering::Equal => true,
-                Ordering::Less => n.left.contains(target),
-                Ordering::Greater => n.right.contains(target),
-            },
-            None => false,
+    pub fn contains<Q>(&self, target: &Q) -> bool
+    where
+        T: Borrow<Q>,
+        C: Compare<Q>,
+        Q: ?Sized,
+    {
+        let mut current = self;
+        while let Some(ref n) = current.0 {
+            current = match C::compare(target, n.value.borrow()) {
+                Ordering::Equal => return true,
+                Ordering::Less => &n.left,
+                Ordering::Greater => &n.right,
+            };
         }
+
+        false
     }
 
     /// Inserts `tree` at the corresponding place in `self`.
-    /// Returns a `TreeOpError` Result if the function failed to insert `tree`.
-    pub fn add_to_end(&mut self, tree: Tree<T>) -> Result<(), TreeOpError> {
+    /// Returns a `TreeOpError` Result if the function failed to insert `tree`,
+    /// giving `tree` back when its root value was already in `self`.
+    pub fn add_to_end(&mut self, tree: Self) -> Result<(), TreeOpError<Self>> {
         // Get the value at the root of `tree`
-        let target = if tree.0.is_some() {
-            &tree.0.as_ref().unwrap().value
-        } else {
-            return Err(TreeOpError::NoneTree);
+        let target = match tree.0 {
+            Some(ref n) => &n.value,
+            None => return Err(TreeOpError::NoneTree),
         };
 
-        match self.0 {
-            Some(ref mut n) => match target.cmp(&n.value) {
-                Ordering::Equal => Err(TreeOpError::ValueAlreadyExists),
-                Ordering::Less => n.left.add_to_end(tree),
-                Ordering::Greater => n.right.add_to_end(tree),
-            },
+        let slot = self.find_slot::<T>(target);
+        match slot.0 {
+            Some(_) => Err(TreeOpError::ValueAlreadyExists(tree)),
             None => {
-                *self = tree;
+                *slot = tree;
                 Ok(())
             }
         }
     }
 
-    /// Deletes `value` from the tree.
+    /// Deletes `value` from the tree, whatever its number of occurrences.
+    /// When the value is not found the tree, `TreeOpError::NoValue` is returned.
+    pub fn delete<Q>(&mut self, target: &Q) -> Result<(), TreeOpError>
+    where
+        T: Borrow<Q>,
+        C: Compare<Q>,
+        Q: ?Sized,
+    {
+        self.find_slot(target).remove_root()
+    }
+
+    /// Deletes a single occurrence of `value` from the tree, see
+    /// `Duplicates::Count`.
     /// When the value is not found the tree, `TreeOpError::NoValue` is returned.
-    pub fn delete(&mut self, target: &T) -> Result<(), TreeOpError> {
+    pub fn delete_one<Q>(&mut self, target: &Q) -> Result<(), TreeOpError>
+    where
+        T: Borrow<Q>,
+        C: Compare<Q>,
+        Q: ?Sized,
+    {
+        let slot = self.find_slot(target);
+        match slot.0 {
+            Some(ref mut n) if n.count > 1 => {
+                n.count -= 1;
+                Ok(())
+            }
+            _ => slot.remove_root(),
+        }
+    }
+
+    /// Removes the root node of the tree, replacing it by its in-order
+    /// predecessor.
+    fn remove_root(&mut self) -> Result<(), TreeOpError> {
+        let slot = self;
+
         // Destructure `n` thanks to default binding modes and get mutable references
         // on each field of `n`
         //
@@ -106,64 +299,115 @@ where
         // Some(n) = n.as_mut(), // `n` is of type `&mut Node<T>`
         // ```
         // We can then destructure `n` into its fiels: value, left and right
-        let Node { value, left, right } = match &mut self.0 {
+        let Node {
+            value,
+            count,
+            left,
+            right,
+            ..
+        } = match &mut slot.0 {
             Some(n) => n.as_mut(),
             None => return Err(TreeOpError::NoValue),
         };
 
-        match target.cmp(value) {
-            Ordering::Equal => {
-                match left.inorder_predecessor() {
-                    Some(predecessor) => *value = predecessor,
-                    None => self.0 = right.0.take(),
-                }
-                Ok(())
+        match left.take_max() {
+            Some(predecessor) => {
+                *value = predecessor.value;
+                *count = predecessor.count;
             }
-            Ordering::Less => left.delete(target),
-            Ordering::Greater => right.delete(target),
+            None => slot.0 = right.0.take(),
         }
+        Ok(())
     }
 
+    /// Removes and returns the largest value of the tree, i.e. the in-order
+    /// predecessor of the parent of `self` when `self` is a left sub-tree.
     pub fn inorder_predecessor(&mut self) -> Option<T> {
-        match &mut self.0 {
-            Some(n) => match n.right.inorder_predecessor() {
-                Some(child) => Some(child),
-                None => {
-                    let predecessor = self.0.take().unwrap();
-                    self.0 = predecessor.left.0;
-                    Some(predecessor.value)
-                }
-            },
-            None => None,
+        self.take_max().map(|n| n.value)
+    }
+
+    /// Detaches the node holding the largest value of the tree, replacing it
+    /// by its left sub-tree.
+    fn take_max(&mut self) -> Option<Box<Node<T, C>>> {
+        let mut slot = self;
+        while slot.0.as_ref()?.right.0.is_some() {
+            slot = &mut slot.0.as_mut().unwrap().right;
+        }
+
+        let mut max = slot.0.take().unwrap();
+        slot.0 = max.left.0.take();
+        Some(max)
+    }
+}
+
+impl<T, C> Drop for Tree<T, C> {
+    /// Frees the nodes one by one: the default recursive drop would overflow
+    /// the stack on degenerate trees.
+    fn drop(&mut self) {
+        let mut stack: Vec<Box<Node<T, C>>> = self.0.take().into_iter().collect();
+        while let Some(mut n) = stack.pop() {
+            stack.extend(n.left.0.take());
+            stack.extend(n.right.0.take());
         }
     }
 }
 
-impl<T> Default for Tree<T>
+impl<T, C> Default for Tree<T, C> {
+    fn default() -> Self {
+        Tree(None)
+    }
+}
+
+impl<T, C> Tree<T, C>
 where
-    T: Ord + Debug,
+    C: Compare<T>,
 {
-    fn default() -> Self {
-        Self::new()
+    /// Returns true iff both trees contain the same values, whatever their
+    /// shape. Use `==` to also compare the shapes.
+    pub fn set_eq(&self, other: &Self) -> bool {
+        let mut a = self.iter();
+        let mut b = other.iter();
+        loop {
+            match (a.next(), b.next()) {
+                (Some(x), Some(y)) if C::compare(x, y) == Ordering::Equal => {}
+                (None, None) => return true,
+                _ => return false,
+            }
+        }
     }
 }
 
-impl<T> PartialEq for Tree<T>
+impl<T, C> PartialEq for Tree<T, C>
 where
     T: PartialEq,
 {
-    /// Required to be able to compare `Tree`s together.
+    /// Structural equality: both trees have the same shape and the same value
+    /// at every node. Trees holding the same values with different shapes are
+    /// not equal, see `set_eq`.
     fn eq(&self, other: &Self) -> bool {
-        match (self.0.as_ref(), other.0.as_ref()) {
-            (Some(a), Some(b)) => a.value == b.value,
-            (None, None) => true,
-            _ => false,
+        let mut stack = vec![(self, other)];
+        while let Some((a, b)) = stack.pop() {
+            match (a.0.as_ref(), b.0.as_ref()) {
+                (Some(a), Some(b)) => {
+                    if a.value != b.value || a.count != b.count {
+                        return false;
+                    }
+                    stack.push((&a.left, &b.left));
+                    stack.push((&a.right, &b.right));
+                }
+                (None, None) => {}
+                _ => return false,
+            }
         }
+
+        true
     }
 }
 
+impl<T, C> Eq for Tree<T, C> where T: Eq {}
+
 #[cfg(test)]
-mod test {
+pub(crate) mod test {
     use super::*;
 
     #[test]
@@ -175,34 +419,229 @@ mod test {
     #[test]
     fn inserts() {
         let mut bst = Tree::new();
-        assert!(bst.insert(2).is_ok());
+        assert!(bst.checked(|t| t.insert(2)).is_ok());
     }
 
     #[test]
     fn leaf() {
         let mut bst = Tree::new();
-        bst.insert(2).expect("Failed to insert");
+        bst.checked(|t| t.insert(2)).expect("Failed to insert");
         assert_eq!(bst, Tree::leaf(2));
     }
 
     #[test]
+    #[allow(clippy::bool_assert_comparison)]
     fn contain() {
         let mut bst = Tree::new();
-        bst.insert(2).expect("Failed to insert");
-        bst.insert(23).expect("Failed to insert");
-        bst.insert(20).expect("Failed to insert");
-        assert_eq!(bst.contains(23), true);
-        assert_eq!(bst.contains(29), false);
+        bst.checked(|t| t.insert(2)).expect("Failed to insert");
+        bst.checked(|t| t.insert(23)).expect("Failed to insert");
+        bst.checked(|t| t.insert(20)).expect("Failed to insert");
+        assert_eq!(bst.contains(&23), true);
+        assert_eq!(bst.contains(&29), false);
     }
 
     #[test]
     fn remove() {
         let mut bst = Tree::new();
-        bst.insert(2).expect("Failed to insert");
-        bst.insert(23).expect("Failed to insert");
-        bst.insert(20).expect("Failed to insert");
-        assert!(bst.delete(&2).is_ok());
-        assert!(bst.delete(&23).is_ok());
-        assert!(bst.delete(&2).is_err());
+        bst.checked(|t| t.insert(2)).expect("Failed to insert");
+        bst.checked(|t| t.insert(23)).expect("Failed to insert");
+        bst.checked(|t| t.insert(20)).expect("Failed to insert");
+        assert!(bst.checked(|t| t.delete(&2)).is_ok());
+        assert!(bst.checked(|t| t.delete(&23)).is_ok());
+        assert!(bst.checked(|t| t.delete(&2)).is_err());
+    }
+
+    #[test]
+    fn compares_structure() {
+        let mut a = Tree::new();
+        let mut b = Tree::new();
+        for v in [2, 1, 3] {
+            a.checked(|t| t.insert(v)).expect("Failed to insert");
+        }
+        for v in [2, 3, 4] {
+            b.checked(|t| t.insert(v)).expect("Failed to insert");
+        }
+        assert_ne!(a, b);
+        assert!(!a.set_eq(&b));
+
+        b.checked(|t| t.delete(&4)).expect("Failed to delete");
+        b.checked(|t| t.insert(1)).expect("Failed to insert");
+        assert_eq!(a, b);
+    }
+
+    #[test]
+    fn compares_sets() {
+        let mut a = Tree::new();
+        let mut b = Tree::new();
+        for v in [2, 1, 3] {
+            a.checked(|t| t.insert(v)).expect("Failed to insert");
+        }
+        for v in [1, 2, 3] {
+            b.checked(|t| t.insert(v)).expect("Failed to insert");
+        }
+        assert_ne!(a, b);
+        assert!(a.set_eq(&b));
+    }
+
+    #[test]
+    fn rejects_duplicates() {
+        let mut bst = Tree::new();
+        bst.checked(|t| t.insert_with(2, Duplicates::Reject))
+            .expect("Failed to insert");
+        assert!(bst
+            .checked(|t| t.insert_with(2, Duplicates::Reject))
+            .is_err());
+        assert_eq!(bst.count(&2), 1);
+        assert_eq!(bst.count(&3), 0);
+    }
+
+    #[test]
+    fn reports_errors() {
+        let mut bst = Tree::new();
+        bst.checked(|t| t.insert(String::from("a")))
+            .expect("Failed to insert");
+        let e = bst.checked(|t| t.insert(String::from("a"))).unwrap_err();
+        assert_eq!(e.to_string(), "value \"a\" already exists in the tree");
+        assert_eq!(e.into_value(), Some(String::from("a")));
+
+        let e = bst.checked(|t| t.delete("b")).unwrap_err();
+        assert_eq!(e, TreeOpError::NoValue);
+        assert_eq!(e.to_string(), "value not found in the tree");
+        let e = bst.checked(|t| t.add_to_end(Tree::new())).unwrap_err();
+        assert_eq!(e.to_string(), "cannot add an empty tree");
+
+        // Errors propagate with `?` into a `Box<dyn Error>`
+        fn fill(bst: &mut Tree<i32>) -> Result<(), Box<dyn Error>> {
+            bst.insert(1)?;
+            bst.delete(&2)?;
+            Ok(())
+        }
+        let e = fill(&mut Tree::new()).unwrap_err();
+        assert_eq!(e.to_string(), "value not found in the tree");
+    }
+
+    #[test]
+    fn replaces_duplicates() {
+        // Only ordered by the key, so that replacing is observable
+        #[derive(Debug)]
+        struct Entry(i32, &'static str);
+        impl PartialEq for Entry {
+            fn eq(&self, other: &Self) -> bool {
+                self.0 == other.0
+            }
+        }
+        impl Eq for Entry {}
+        impl PartialOrd for Entry {
+            fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+                Some(self.cmp(other))
+            }
+        }
+        impl Ord for Entry {
+            fn cmp(&self, other: &Self) -> Ordering {
+                self.0.cmp(&other.0)
+            }
+        }
+
+        let mut bst = Tree::new();
+        bst.checked(|t| t.insert(Entry(1, "old")))
+            .expect("Failed to insert");
+        let old = bst.checked(|t| t.insert_with(Entry(1, "new"), Duplicates::Replace));
+        assert_eq!(old.expect("Failed to insert").map(|e| e.1), Some("old"));
+        assert_eq!(bst.iter().next().map(|e| e.1), Some("new"));
+        assert_eq!(bst.count(&Entry(1, "")), 1);
+    }
+
+    #[test]
+    fn counts_duplicates() {
+        let mut bst = Tree::new();
+        for v in [5, 3, 5, 8, 5, 3] {
+            bst.checked(|t| t.insert_with(v, Duplicates::Count))
+                .expect("Failed to insert");
+        }
+        assert_eq!(bst.count(&5), 3);
+        assert_eq!(bst.count(&3), 2);
+        assert_eq!(bst.count(&8), 1);
+
+        bst.checked(|t| t.delete_one(&5)).expect("Failed to delete");
+        assert_eq!(bst.count(&5), 2);
+        // The root moves to its predecessor with its multiplicity
+        bst.checked(|t| t.delete(&5)).expect("Failed to delete");
+        assert_eq!(bst.count(&5), 0);
+        assert_eq!(bst.count(&3), 2);
+        bst.checked(|t| t.delete_one(&3)).expect("Failed to delete");
+        bst.checked(|t| t.delete_one(&3)).expect("Failed to delete");
+        bst.checked(|t| t.delete_one(&3))
+            .expect_err("Value is not in the tree");
+        assert!(bst.iter().copied().eq([8]));
+    }
+
+    /// Builds the degenerate tree obtained by inserting `0..n` in order, in
+    /// O(n): inserting them one by one would take O(n²). Shared by the tests
+    /// of every module.
+    pub(crate) fn sorted_chain(n: u32) -> Tree<u32> {
+        let mut bst = Tree::new();
+        for value in (0..n).rev() {
+            bst = Tree(Some(Box::new(Node {
+                value,
+                count: 1,
+                left: Tree(None),
+                right: bst,
+                order: PhantomData,
+            })));
+        }
+        bst
+    }
+
+    /// Builds a tree by inserting `values` in order, checking it after each
+    /// insertion. Most tests use `[15, 10, 20, 8, 12, 18, 30]`, that is:
+    /// ```text
+    ///         15
+    ///       /    \
+    ///     10      20
+    ///    /  \    /  \
+    ///   8   12  18  30
+    /// ```
+    pub(crate) fn tree(values: &[i32]) -> Tree<i32> {
+        let mut bst = Tree::new();
+        for v in values {
+            bst.checked(|t| t.insert(*v)).expect("Failed to insert");
+        }
+        bst
+    }
+
+    #[test]
+    fn inserts_sorted() {
+        let n = 10_000;
+        let mut bst = Tree::new();
+        // Validated once: after every insertion, this would be quadratic
+        for i in 0..n {
+            bst.insert(i).expect("Failed to insert");
+        }
+        assert_eq!(bst.validate(), Ok(()));
+        assert!(bst.iter().copied().eq(0..n));
+        assert!(bst.contains(&(n - 1)));
+    }
+
+    #[test]
+    fn handles_million_sorted_keys() {
+        let n = 1_000_000;
+        let mut bst = sorted_chain(n);
+
+        bst.checked(|t| t.insert(n)).expect("Failed to insert");
+        bst.checked(|t| t.insert(n - 1))
+            .expect_err("Value was already in the tree");
+        assert!(bst.contains(&(n - 1)));
+        assert!(!bst.contains(&(n + 1)));
+
+        bst.checked(|t| t.add_to_end(Tree::leaf(n + 1)))
+            .expect("Failed to add");
+        bst.checked(|t| t.delete(&(n / 2)))
+            .expect("Failed to delete");
+        bst.checked(|t| t.delete(&(n + 1)))
+            .expect("Failed to delete");
+        bst.checked(|t| t.delete(&(n + 1)))
+            .expect_err("Value is not in the tree");
+        assert!(!bst.contains(&(n / 2)));
+        assert_eq!(bst.iter().count(), n as usize);
     }
 }
@@ -0,0 +1,165 @@
+use crate::{Compare, Tree};
+use std::error::Error;
+use std::fmt;
+use std::fs::File;
+use std::io::{self, BufRead, BufReader};
+use std::path::Path;
+use std::str::FromStr;
+
+/// Errors returned when loading the values of a `Tree` from a file.
+#[derive(Debug)]
+pub enum LoadError<E> {
+    /// The input could not be read.
+    Io(io::Error),
+    /// `token` could not be parsed, at the given line and column (from 1).
+    Parse {
+        line: usize,
+        column: usize,
+        token: String,
+        error: E,
+    },
+}
+
+impl<E: fmt::Display> fmt::Display for LoadError<E> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LoadError::Io(e) => write!(f, "cannot read input: {}", e),
+            LoadError::Parse {
+                line,
+                column,
+                token,
+                error,
+            } => write!(
+                f,
+                "{}:{}: invalid value `{}`: {}",
+                line, column, token, error
+            ),
+        }
+    }
+}
+
+impl<E> Error for LoadError<E>
+where
+    E: Error + 'static,
+{
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            LoadError::Io(e) => Some(e),
+            LoadError::Parse { error, .. } => Some(error),
+        }
+    }
+}
+
+impl<E> From<io::Error> for LoadError<E> {
+    fn from(e: io::Error) -> Self {
+        LoadError::Io(e)
+    }
+}
+
+/// Reads the whitespace-separated values of `reader`, in order.
+///
+/// Lines starting with `#`, possibly after blanks, are comments. Reading
+/// stops at the first value that can not be parsed.
+pub fn read_values<T, R>(reader: R) -> Result<Vec<T>, LoadError<T::Err>>
+where
+    T: FromStr,
+    R: BufRead,
+{
+    let mut values = Vec::new();
+    for (i, line) in reader.lines().enumerate() {
+        let line = line?;
+        if line.trim_start().starts_with('#') {
+            continue;
+        }
+
+        let mut rest = line.as_str();
+        while let Some(start) = rest.find(|c: char| !c.is_whitespace()) {
+            let len = rest[start..]
+                .find(char::is_whitespace)
+                .unwrap_or(rest.len() - start);
+            let token = &rest[start..start + len];
+            let value = token.parse().map_err(|error| LoadError::Parse {
+                line: i + 1,
+                column: line[..line.len() - rest.len() + start].chars().count() + 1,
+                token: token.to_string(),
+                error,
+            })?;
+            values.push(value);
+            rest = &rest[start + len..];
+        }
+    }
+
+    Ok(values)
+}
+
+/// Loading from text files, see `read_values` for the format.
+///
+/// The tree is built with `FromIterator`: it is balanced whatever the order
+/// of the values, and duplicates are ignored.
+impl<T, C> Tree<T, C>
+where
+    T: FromStr,
+    C: Compare<T>,
+{
+    /// Builds a tree from the values of `reader`.
+    pub fn from_reader<R: BufRead>(reader: R) -> Result<Self, LoadError<T::Err>> {
+        Ok(read_values(reader)?.into_iter().collect())
+    }
+
+    /// Builds a tree from the values of the file at `path`, or of stdin when
+    /// `path` is `-`.
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self, LoadError<T::Err>> {
+        let path = path.as_ref();
+        if path == Path::new("-") {
+            Self::from_reader(io::stdin().lock())
+        } else {
+            Self::from_reader(BufReader::new(File::open(path)?))
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn reads_values() {
+        let text = "# Values of the tree\n15 10\t20\n\n   # indented comment\n 8 12\n";
+        let values: Vec<u32> = read_values(text.as_bytes()).expect("Failed to read");
+        assert_eq!(values, [15, 10, 20, 8, 12]);
+
+        let bst: Tree<u32> = Tree::from_reader(text.as_bytes()).expect("Failed to load");
+        assert!(bst.iter().copied().eq([8, 10, 12, 15, 20]));
+        assert_eq!(bst.height(), 3);
+
+        let words = Tree::<String>::from_reader("b a c a".as_bytes());
+        assert_eq!(words.expect("Failed to load").len(), 3);
+    }
+
+    #[test]
+    fn reports_invalid_tokens() {
+        let text = "1 2\n# 3 x\n4  éa5 6\n";
+        match read_values::<u32, _>(text.as_bytes()) {
+            Err(LoadError::Parse {
+                line,
+                column,
+                token,
+                ..
+            }) => assert_eq!((line, column, token.as_str()), (3, 4, "éa5")),
+            other => panic!("Unexpected result {:?}", other),
+        }
+
+        let e = Tree::<i8>::from_reader("12\n  300".as_bytes()).unwrap_err();
+        assert_eq!(
+            e.to_string(),
+            "2:3: invalid value `300`: number too large to fit in target type"
+        );
+        assert!(e.source().is_some());
+    }
+
+    #[test]
+    fn reports_missing_files() {
+        let e = Tree::<u32>::load("/nonexistent/values.txt").unwrap_err();
+        assert!(matches!(e, LoadError::Io(_)));
+    }
+}
@@ -0,0 +1,492 @@
+use crate::TreeOpError;
+use std::cmp::Ordering;
+
+/// Ordered map, built as a binary search tree on the keys.
+///
+/// Uses the same node design as `Tree`: every node owns a key, its associated
+/// value and the left and right sub-trees.
+#[derive(Debug)]
+pub struct TreeMap<K, V>(Option<Box<Node<K, V>>>);
+
+/// Internal Node representation with a `key`, its `value` and the left and
+/// right sub-trees.
+#[derive(Debug)]
+struct Node<K, V> {
+    key: K,
+    value: V,
+    left: TreeMap<K, V>,
+    right: TreeMap<K, V>,
+}
+
+/// A view into a single entry of a `TreeMap`, returned by `TreeMap::entry`.
+pub enum Entry<'a, K, V> {
+    Occupied(OccupiedEntry<'a, K, V>),
+    Vacant(VacantEntry<'a, K, V>),
+}
+
+/// An entry whose key is present in the map.
+pub struct OccupiedEntry<'a, K, V> {
+    /// Sub-tree whose root holds the key.
+    slot: &'a mut TreeMap<K, V>,
+}
+
+/// An entry whose key is missing from the map.
+pub struct VacantEntry<'a, K, V> {
+    key: K,
+    /// Empty sub-tree where the key belongs.
+    slot: &'a mut TreeMap<K, V>,
+}
+
+impl<K, V> TreeMap<K, V>
+where
+    K: Ord,
+{
+    /// Returns an empty map.
+    pub fn new() -> Self {
+        TreeMap(None)
+    }
+
+    /// Returns the sub-tree whose root holds `key`, or the empty sub-tree
+    /// where `key` would be inserted.
+    fn find_slot(&mut self, key: &K) -> &mut Self {
+        let mut slot = self;
+        loop {
+            let ordering = match slot.0 {
+                Some(ref n) => key.cmp(&n.key),
+                None => return slot,
+            };
+            if ordering == Ordering::Equal {
+                return slot;
+            }
+
+            // Looked up again to end the shared borrow before moving down
+            let n = slot.0.as_mut().unwrap();
+            slot = if ordering == Ordering::Less {
+                &mut n.left
+            } else {
+                &mut n.right
+            };
+        }
+    }
+
+    /// Returns the entry of `key` for in-place manipulation.
+    pub fn entry(&mut self, key: K) -> Entry<'_, K, V> {
+        let slot = self.find_slot(&key);
+        match slot.0 {
+            Some(_) => Entry::Occupied(OccupiedEntry { slot }),
+            None => Entry::Vacant(VacantEntry { key, slot }),
+        }
+    }
+
+    /// Inserts `value` for `key`.
+    /// Returns the previous value associated with `key`, if any.
+    pub fn insert(&mut self, key: K, value: V) -> Option<V> {
+        match self.entry(key) {
+            Entry::Occupied(mut e) => Some(e.insert(value)),
+            Entry::Vacant(e) => {
+                e.insert(value);
+                None
+            }
+        }
+    }
+
+    /// Inserts `value` for `key` only if `key` is not already in the map.
+    /// Returns `TreeOpError::ValueAlreadyExists` with `value` otherwise, like
+    /// `Tree::insert`.
+    pub fn try_insert(&mut self, key: K, value: V) -> Result<(), TreeOpError<V>> {
+        match self.entry(key) {
+            Entry::Occupied(_) => Err(TreeOpError::ValueAlreadyExists(value)),
+            Entry::Vacant(e) => {
+                e.insert(value);
+                Ok(())
+            }
+        }
+    }
+
+    /// Returns true iff `key` belongs to the map.
+    pub fn contains_key(&self, key: &K) -> bool {
+        self.get(key).is_some()
+    }
+
+    /// Returns a reference to the value associated with `key`.
+    pub fn get(&self, key: &K) -> Option<&V> {
+        let mut current = self;
+        while let Some(ref n) = current.0 {
+            current = match key.cmp(&n.key) {
+                Ordering::Equal => return Some(&n.value),
+                Ordering::Less => &n.left,
+                Ordering::Greater => &n.right,
+            };
+        }
+
+        None
+    }
+
+    /// Returns a mutable reference to the value associated with `key`.
+    pub fn get_mut(&mut self, key: &K) -> Option<&mut V> {
+        self.find_slot(key).0.as_mut().map(|n| &mut n.value)
+    }
+
+    /// Removes `key` from the map and returns its value.
+    /// When the key is not found in the map, `TreeOpError::NoValue` is returned.
+    pub fn remove(&mut self, key: &K) -> Result<V, TreeOpError> {
+        match self.find_slot(key).remove_root() {
+            Some((_, value)) => Ok(value),
+            None => Err(TreeOpError::NoValue),
+        }
+    }
+
+    /// Removes the root of the tree, replacing it by its in-order predecessor.
+    fn remove_root(&mut self) -> Option<(K, V)> {
+        let n = self.0.as_mut()?;
+        match n.left.take_max() {
+            Some((key, value)) => Some((
+                std::mem::replace(&mut n.key, key),
+                std::mem::replace(&mut n.value, value),
+            )),
+            None => {
+                let mut n = self.0.take().unwrap();
+                self.0 = n.right.0.take();
+                Some((n.key, n.value))
+            }
+        }
+    }
+
+    /// Removes and returns the entry with the largest key.
+    fn take_max(&mut self) -> Option<(K, V)> {
+        let mut slot = self;
+        while slot.0.as_ref()?.right.0.is_some() {
+            slot = &mut slot.0.as_mut().unwrap().right;
+        }
+
+        let mut n = slot.0.take().unwrap();
+        slot.0 = n.left.0.take();
+        Some((n.key, n.value))
+    }
+}
+
+impl<K, V> TreeMap<K, V> {
+    /// Returns an iterator over the entries of the map, by increasing key.
+    pub fn iter(&self) -> Iter<'_, K, V> {
+        let mut iter = Iter { stack: Vec::new() };
+        iter.push_left_spine(self);
+        iter
+    }
+
+    /// Returns an iterator over the entries of the map, by increasing key,
+    /// with mutable references to the values. Keys can not be modified since
+    /// that could break the ordering of the tree.
+    pub fn iter_mut(&mut self) -> IterMut<'_, K, V> {
+        let mut iter = IterMut { stack: Vec::new() };
+        iter.push_left_spine(self);
+        iter
+    }
+}
+
+impl<K, V> Drop for TreeMap<K, V> {
+    /// Frees the nodes one by one, like `Tree`: the default recursive drop
+    /// would overflow the stack on degenerate maps.
+    fn drop(&mut self) {
+        let mut stack: Vec<Box<Node<K, V>>> = self.0.take().into_iter().collect();
+        while let Some(mut n) = stack.pop() {
+            stack.extend(n.left.0.take());
+            stack.extend(n.right.0.take());
+        }
+    }
+}
+
+impl<K, V> Default for TreeMap<K, V>
+where
+    K: Ord,
+{
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<'a, K, V> Entry<'a, K, V>
+where
+    K: Ord,
+{
+    /// Returns the key of the entry.
+    pub fn key(&self) -> &K {
+        match self {
+            Entry::Occupied(e) => e.key(),
+            Entry::Vacant(e) => &e.key,
+        }
+    }
+
+    /// Inserts `default` if the entry is vacant and returns a mutable reference
+    /// to the value.
+    pub fn or_insert(self, default: V) -> &'a mut V {
+        self.or_insert_with(|| default)
+    }
+
+    /// Inserts the result of `default` if the entry is vacant and returns a
+    /// mutable reference to the value.
+    pub fn or_insert_with<F: FnOnce() -> V>(self, default: F) -> &'a mut V {
+        match self {
+            Entry::Occupied(e) => e.into_mut(),
+            Entry::Vacant(e) => e.insert(default()),
+        }
+    }
+
+    /// Applies `f` to the value if the entry is occupied.
+    pub fn and_modify<F: FnOnce(&mut V)>(mut self, f: F) -> Self {
+        if let Entry::Occupied(ref mut e) = self {
+            f(e.get_mut());
+        }
+        self
+    }
+}
+
+impl<'a, K, V> Entry<'a, K, V>
+where
+    K: Ord,
+    V: Default,
+{
+    /// Inserts the default value if the entry is vacant and returns a mutable
+    /// reference to the value.
+    pub fn or_default(self) -> &'a mut V {
+        self.or_insert_with(V::default)
+    }
+}
+
+impl<'a, K, V> OccupiedEntry<'a, K, V>
+where
+    K: Ord,
+{
+    fn node(&self) -> &Node<K, V> {
+        self.slot
+            .0
+            .as_ref()
+            .expect("occupied entry of an empty slot")
+    }
+
+    fn node_mut(&mut self) -> &mut Node<K, V> {
+        self.slot
+            .0
+            .as_mut()
+            .expect("occupied entry of an empty slot")
+    }
+
+    pub fn key(&self) -> &K {
+        &self.node().key
+    }
+
+    pub fn get(&self) -> &V {
+        &self.node().value
+    }
+
+    pub fn get_mut(&mut self) -> &mut V {
+        &mut self.node_mut().value
+    }
+
+    /// Converts the entry into a mutable reference to its value, bound to the
+    /// lifetime of the map.
+    pub fn into_mut(self) -> &'a mut V {
+        &mut self.slot.0.as_mut().unwrap().value
+    }
+
+    /// Replaces the value of the entry and returns the old one.
+    pub fn insert(&mut self, value: V) -> V {
+        std::mem::replace(self.get_mut(), value)
+    }
+
+    /// Removes the entry from the map and returns its value.
+    pub fn remove(self) -> V {
+        self.slot.remove_root().unwrap().1
+    }
+}
+
+impl<'a, K, V> VacantEntry<'a, K, V>
+where
+    K: Ord,
+{
+    pub fn key(&self) -> &K {
+        &self.key
+    }
+
+    /// Inserts `value` for the key of the entry and returns a mutable reference
+    /// to it.
+    pub fn insert(self, value: V) -> &'a mut V {
+        *self.slot = TreeMap(Some(Box::new(Node {
+            key: self.key,
+            value,
+            left: TreeMap(None),
+            right: TreeMap(None),
+        })));
+        &mut self.slot.0.as_mut().unwrap().value
+    }
+}
+
+/// In-order iterator over the entries of a `TreeMap`.
+pub struct Iter<'a, K, V> {
+    stack: Vec<&'a Node<K, V>>,
+}
+
+impl<'a, K, V> Iter<'a, K, V> {
+    fn push_left_spine(&mut self, mut tree: &'a TreeMap<K, V>) {
+        while let Some(ref n) = tree.0 {
+            self.stack.push(n);
+            tree = &n.left;
+        }
+    }
+}
+
+impl<'a, K, V> Iterator for Iter<'a, K, V> {
+    type Item = (&'a K, &'a V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let n = self.stack.pop()?;
+        self.push_left_spine(&n.right);
+        Some((&n.key, &n.value))
+    }
+}
+
+/// In-order iterator over the entries of a `TreeMap`, with mutable values.
+pub struct IterMut<'a, K, V> {
+    /// Key, value and right sub-tree of the nodes on the current path.
+    stack: Vec<(&'a K, &'a mut V, &'a mut TreeMap<K, V>)>,
+}
+
+impl<'a, K, V> IterMut<'a, K, V> {
+    fn push_left_spine(&mut self, mut tree: &'a mut TreeMap<K, V>) {
+        while let Some(n) = tree.0.as_deref_mut() {
+            let Node {
+                key,
+                value,
+                left,
+                right,
+            } = n;
+            self.stack.push((key, value, right));
+            tree = left;
+        }
+    }
+}
+
+impl<'a, K, V> Iterator for IterMut<'a, K, V> {
+    type Item = (&'a K, &'a mut V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let (key, value, right) = self.stack.pop()?;
+        self.push_left_spine(right);
+        Some((key, value))
+    }
+}
+
+impl<'a, K, V> IntoIterator for &'a TreeMap<K, V> {
+    type Item = (&'a K, &'a V);
+    type IntoIter = Iter<'a, K, V>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl<'a, K, V> IntoIterator for &'a mut TreeMap<K, V> {
+    type Item = (&'a K, &'a mut V);
+    type IntoIter = IterMut<'a, K, V>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter_mut()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn inserts() {
+        let mut map = TreeMap::new();
+        assert_eq!(map.insert(2, "two"), None);
+        assert_eq!(map.insert(1, "one"), None);
+        assert_eq!(map.insert(2, "deux"), Some("two"));
+        assert_eq!(map.get(&2), Some(&"deux"));
+        assert_eq!(map.get(&3), None);
+        assert!(map.try_insert(1, "un").is_err());
+        assert!(map.try_insert(3, "three").is_ok());
+    }
+
+    #[test]
+    fn gets_mut() {
+        let mut map = TreeMap::new();
+        map.insert("a", 1);
+        *map.get_mut(&"a").unwrap() += 41;
+        assert_eq!(map.get(&"a"), Some(&42));
+        assert_eq!(map.get_mut(&"b"), None);
+    }
+
+    #[test]
+    fn remove() {
+        let mut map = TreeMap::new();
+        for (k, v) in [(15, 'a'), (10, 'b'), (20, 'c'), (8, 'd'), (12, 'e')] {
+            map.insert(k, v);
+        }
+        assert_eq!(map.remove(&10).ok(), Some('b'));
+        assert_eq!(map.remove(&15).ok(), Some('a'));
+        assert!(map.remove(&15).is_err());
+        assert!(!map.contains_key(&10));
+        let keys: Vec<_> = map.iter().map(|(k, _)| *k).collect();
+        assert_eq!(keys, vec![8, 12, 20]);
+    }
+
+    #[test]
+    fn counts_with_entries() {
+        let mut counts = TreeMap::new();
+        for word in "the cat and the dog and the bird".split_whitespace() {
+            *counts.entry(word).or_insert(0) += 1;
+        }
+        let counts: Vec<_> = counts.iter().map(|(k, v)| (*k, *v)).collect();
+        assert_eq!(
+            counts,
+            vec![("and", 2), ("bird", 1), ("cat", 1), ("dog", 1), ("the", 3)]
+        );
+    }
+
+    #[test]
+    fn manipulates_entries() {
+        let mut map: TreeMap<i32, Vec<i32>> = TreeMap::new();
+        map.entry(1).or_default().push(1);
+        map.entry(1).and_modify(|v| v.push(2)).or_default();
+        assert_eq!(map.get(&1), Some(&vec![1, 2]));
+
+        match map.entry(1) {
+            Entry::Occupied(e) => assert_eq!(e.remove(), vec![1, 2]),
+            Entry::Vacant(_) => panic!("entry should be occupied"),
+        }
+        assert_eq!(map.entry(1).key(), &1);
+        assert!(matches!(map.entry(1), Entry::Vacant(_)));
+    }
+
+    #[test]
+    fn iterates_mut() {
+        let mut map = TreeMap::new();
+        for k in [3, 1, 2] {
+            map.insert(k, k * 10);
+        }
+        for (_, v) in map.iter_mut() {
+            *v += 1;
+        }
+        let entries: Vec<_> = map.iter().map(|(k, v)| (*k, *v)).collect();
+        assert_eq!(entries, vec![(1, 11), (2, 21), (3, 31)]);
+    }
+
+    #[test]
+    fn drops_degenerate_maps() {
+        // The map obtained by inserting the keys in order, built directly:
+        // inserting them one by one would take O(n²)
+        let mut map = TreeMap::new();
+        for key in (0..1_000_000).rev() {
+            map = TreeMap(Some(Box::new(Node {
+                key,
+                value: (),
+                left: TreeMap(None),
+                right: map,
+            })));
+        }
+        assert_eq!(map.iter().count(), 1_000_000);
+        drop(map);
+    }
+}
@@ -0,0 +1,351 @@
+use crate::{Compare, Natural, TreeOpError};
+use std::borrow::Borrow;
+use std::cmp::Ordering;
+use std::marker::PhantomData;
+use std::sync::Arc;
+
+/// Persistent binary search tree: every version stays valid and unchanged.
+///
+/// `insert` and `delete` leave `self` untouched and return a new version,
+/// which only copies the nodes on the path to the modified value and shares
+/// every other sub-tree with `self`. Keeping a snapshot is therefore a
+/// `clone` in O(1), and each modification allocates O(height) nodes.
+///
+/// Nodes are reference-counted with `Arc`, so versions can be sent to and
+/// read from other threads.
+#[derive(Debug)]
+pub struct PersistentTree<T, C = Natural> {
+    root: Link<T>,
+    len: usize,
+    order: PhantomData<C>,
+}
+
+type Link<T> = Option<Arc<Node<T>>>;
+
+/// Internal Node representation, shared between versions.
+#[derive(Debug)]
+struct Node<T> {
+    value: T,
+    left: Link<T>,
+    right: Link<T>,
+}
+
+impl<T> Drop for Node<T> {
+    /// Frees the nodes no longer used by any version one by one, like
+    /// `Tree::drop`: the default recursive drop would overflow the stack on
+    /// degenerate trees.
+    fn drop(&mut self) {
+        let mut stack: Vec<Arc<Node<T>>> = self.left.take().into_iter().collect();
+        stack.extend(self.right.take());
+        while let Some(n) = stack.pop() {
+            // Sub-trees still shared with another version are left alone
+            if let Some(mut n) = Arc::into_inner(n) {
+                stack.extend(n.left.take());
+                stack.extend(n.right.take());
+            }
+        }
+    }
+}
+
+impl<T> PersistentTree<T> {
+    /// Returns an empty tree, ordered by the natural order of `T`.
+    pub fn new() -> Self {
+        Self::with_comparator()
+    }
+}
+
+impl<T, C> PersistentTree<T, C> {
+    /// Returns an empty tree, ordered by the comparator `C`.
+    pub fn with_comparator() -> Self {
+        PersistentTree {
+            root: None,
+            len: 0,
+            order: PhantomData,
+        }
+    }
+
+    /// Returns the number of values of the tree.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Returns true iff the tree holds no value.
+    pub fn is_empty(&self) -> bool {
+        self.root.is_none()
+    }
+
+    /// Returns an iterator over the values of the tree, in increasing order.
+    pub fn iter(&self) -> Iter<'_, T> {
+        let mut iter = Iter { stack: Vec::new() };
+        iter.push_left_spine(&self.root);
+        iter
+    }
+}
+
+/// Lookups, which unlike the updates do not need to clone values.
+impl<T, C> PersistentTree<T, C>
+where
+    C: Compare<T>,
+{
+    /// Returns true iff `value` belongs to the tree.
+    pub fn contains<Q>(&self, target: &Q) -> bool
+    where
+        T: Borrow<Q>,
+        C: Compare<Q>,
+        Q: ?Sized,
+    {
+        let mut current = &self.root;
+        while let Some(n) = current {
+            current = match C::compare(target, n.value.borrow()) {
+                Ordering::Equal => return true,
+                Ordering::Less => &n.left,
+                Ordering::Greater => &n.right,
+            };
+        }
+
+        false
+    }
+}
+
+impl<T, C> PersistentTree<T, C>
+where
+    T: Clone,
+    C: Compare<T>,
+{
+    /// Returns a new version of the tree also containing `value`.
+    /// Returns `TreeOpError::ValueAlreadyExists` with `value` iff an equal
+    /// value was already contained in the tree.
+    pub fn insert(&self, value: T) -> Result<Self, TreeOpError<T>> {
+        let mut path = Vec::new();
+        let mut current = &self.root;
+        while let Some(n) = current {
+            let ordering = C::compare(&value, &n.value);
+            current = match ordering {
+                Ordering::Equal => return Err(TreeOpError::ValueAlreadyExists(value)),
+                Ordering::Less => &n.left,
+                Ordering::Greater => &n.right,
+            };
+            path.push((n.as_ref(), ordering));
+        }
+
+        let leaf = Some(Arc::new(Node {
+            value,
+            left: None,
+            right: None,
+        }));
+        Ok(self.version(copy_path(path, leaf), self.len + 1))
+    }
+
+    /// Returns a new version of the tree without `target`.
+    /// When the value is not found the tree, `TreeOpError::NoValue` is returned.
+    pub fn delete<Q>(&self, target: &Q) -> Result<Self, TreeOpError>
+    where
+        T: Borrow<Q>,
+        C: Compare<Q>,
+        Q: ?Sized,
+    {
+        let mut path = Vec::new();
+        let mut current = &self.root;
+        let found = loop {
+            let n = current.as_ref().ok_or(TreeOpError::NoValue)?;
+            let ordering = C::compare(target, n.value.borrow());
+            current = match ordering {
+                Ordering::Equal => break n,
+                Ordering::Less => &n.left,
+                Ordering::Greater => &n.right,
+            };
+            path.push((n.as_ref(), ordering));
+        };
+
+        // Like `Tree::delete`, the value is replaced by its in-order
+        // predecessor, taken from a copy of the left sub-tree
+        let replacement = match found.left {
+            None => found.right.clone(),
+            Some(ref left) => {
+                let (left, predecessor) = take_max(left);
+                Some(Arc::new(Node {
+                    value: predecessor,
+                    left,
+                    right: found.right.clone(),
+                }))
+            }
+        };
+        Ok(self.version(copy_path(path, replacement), self.len - 1))
+    }
+
+    fn version(&self, root: Link<T>, len: usize) -> Self {
+        PersistentTree {
+            root,
+            len,
+            order: PhantomData,
+        }
+    }
+}
+
+/// Copies the nodes of `path`, from the root to the parent of the modified
+/// sub-tree, bottom-up: the copy of each node points to `sub`, the copy of its
+/// child on the side of the `Ordering`, and shares its other child.
+fn copy_path<T: Clone>(path: Vec<(&Node<T>, Ordering)>, mut sub: Link<T>) -> Link<T> {
+    for (n, side) in path.into_iter().rev() {
+        let (left, right) = if side == Ordering::Less {
+            (sub, n.right.clone())
+        } else {
+            (n.left.clone(), sub)
+        };
+        sub = Some(Arc::new(Node {
+            value: n.value.clone(),
+            left,
+            right,
+        }));
+    }
+
+    sub
+}
+
+/// Returns a copy of the tree rooted at `n` without its largest value, and
+/// that value.
+fn take_max<T: Clone>(mut n: &Arc<Node<T>>) -> (Link<T>, T) {
+    let mut path = Vec::new();
+    while let Some(ref right) = n.right {
+        path.push((n.as_ref(), Ordering::Greater));
+        n = right;
+    }
+
+    (copy_path(path, n.left.clone()), n.value.clone())
+}
+
+impl<T, C> Clone for PersistentTree<T, C> {
+    /// Returns a snapshot of the tree, sharing all its nodes, in O(1).
+    fn clone(&self) -> Self {
+        PersistentTree {
+            root: self.root.clone(),
+            len: self.len,
+            order: PhantomData,
+        }
+    }
+}
+
+impl<T, C> Default for PersistentTree<T, C> {
+    fn default() -> Self {
+        Self::with_comparator()
+    }
+}
+
+/// In-order iterator over the values of a `PersistentTree`.
+pub struct Iter<'a, T> {
+    stack: Vec<&'a Node<T>>,
+}
+
+impl<'a, T> Iter<'a, T> {
+    /// Pushes `link` and all its left descendants.
+    fn push_left_spine(&mut self, mut link: &'a Link<T>) {
+        while let Some(n) = link {
+            self.stack.push(n);
+            link = &n.left;
+        }
+    }
+}
+
+impl<'a, T> Iterator for Iter<'a, T> {
+    type Item = &'a T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let n = self.stack.pop()?;
+        self.push_left_spine(&n.right);
+        Some(&n.value)
+    }
+}
+
+impl<'a, T, C> IntoIterator for &'a PersistentTree<T, C> {
+    type Item = &'a T;
+    type IntoIter = Iter<'a, T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn tree(values: &[i32]) -> PersistentTree<i32> {
+        values.iter().fold(PersistentTree::new(), |t, v| {
+            t.insert(*v).expect("Failed to insert")
+        })
+    }
+
+    #[test]
+    fn keeps_versions() {
+        let v1 = tree(&[15, 10, 20, 8, 12]);
+        let v2 = v1.insert(18).expect("Failed to insert");
+        let v3 = v2.delete(&10).expect("Failed to delete");
+        v3.delete(&10).expect_err("Value is not in the tree");
+        v3.insert(15).expect_err("Value was already in the tree");
+
+        assert!(v1.iter().copied().eq([8, 10, 12, 15, 20]));
+        assert!(v2.iter().copied().eq([8, 10, 12, 15, 18, 20]));
+        assert!(v3.iter().copied().eq([8, 12, 15, 18, 20]));
+        assert_eq!((v1.len(), v2.len(), v3.len()), (5, 6, 5));
+        assert!(v2.contains(&18) && !v1.contains(&18));
+    }
+
+    #[test]
+    fn looks_up_unclonable_values() {
+        // Only the updates require `T: Clone`
+        #[derive(PartialEq, Eq, PartialOrd, Ord)]
+        struct Key(u32);
+        assert!(!PersistentTree::<Key>::new().contains(&Key(1)));
+    }
+
+    #[test]
+    fn shares_unchanged_subtrees() {
+        let v1 = tree(&[15, 10, 20, 8, 12, 18, 30]);
+        let v2 = v1.insert(19).expect("Failed to insert");
+
+        let (a, b) = (v1.root.as_ref().unwrap(), v2.root.as_ref().unwrap());
+        assert!(!Arc::ptr_eq(a, b));
+        // The left sub-tree is shared, the path 15 -> 20 -> 18 was copied
+        assert!(Arc::ptr_eq(
+            a.left.as_ref().unwrap(),
+            b.left.as_ref().unwrap()
+        ));
+        let (a, b) = (a.right.as_ref().unwrap(), b.right.as_ref().unwrap());
+        assert!(!Arc::ptr_eq(a, b));
+        assert!(Arc::ptr_eq(
+            a.right.as_ref().unwrap(),
+            b.right.as_ref().unwrap()
+        ));
+    }
+
+    #[test]
+    fn deletes_with_predecessor() {
+        let v1 = tree(&[15, 10, 20, 8, 12, 11]);
+        let v2 = v1.delete(&15).expect("Failed to delete");
+        assert_eq!(v2.root.as_ref().map(|n| n.value), Some(12));
+        assert!(v2.iter().copied().eq([8, 10, 11, 12, 20]));
+        assert!(v1.iter().copied().eq([8, 10, 11, 12, 15, 20]));
+    }
+
+    #[test]
+    fn drops_deep_versions() {
+        let n = 1_000_000;
+        // Sorted insertions copy O(n) nodes each: build the chain directly
+        let mut root = None;
+        for value in (0..n).rev() {
+            root = Some(Arc::new(Node {
+                value,
+                left: None,
+                right: root,
+            }));
+        }
+        let v1 = PersistentTree::<u32> {
+            root,
+            len: n as usize,
+            order: PhantomData,
+        };
+        let v2 = v1.delete(&0).expect("Failed to delete");
+        drop(v1);
+        assert_eq!(v2.iter().count(), n as usize - 1);
+    }
+}
@@ -0,0 +1,183 @@
+use crate::{Compare, Tree};
+use std::borrow::Borrow;
+use std::cmp::Ordering;
+
+/// Ordered navigation and order statistics on a `Tree`. Like lookups, they
+/// accept any borrowed form `Q` of the values.
+impl<T, C> Tree<T, C>
+where
+    C: Compare<T>,
+{
+    /// Returns the smallest value of the tree.
+    pub fn min(&self) -> Option<&T> {
+        let mut n = self.0.as_ref()?;
+        while let Some(ref left) = n.left.0 {
+            n = left;
+        }
+
+        Some(&n.value)
+    }
+
+    /// Returns the largest value of the tree.
+    pub fn max(&self) -> Option<&T> {
+        let mut n = self.0.as_ref()?;
+        while let Some(ref right) = n.right.0 {
+            n = right;
+        }
+
+        Some(&n.value)
+    }
+
+    /// Returns the largest value smaller than or equal to `target`.
+    pub fn floor<Q>(&self, target: &Q) -> Option<&T>
+    where
+        T: Borrow<Q>,
+        C: Compare<Q>,
+        Q: ?Sized,
+    {
+        self.closest(target, Ordering::Less, true)
+    }
+
+    /// Returns the smallest value larger than or equal to `target`.
+    pub fn ceiling<Q>(&self, target: &Q) -> Option<&T>
+    where
+        T: Borrow<Q>,
+        C: Compare<Q>,
+        Q: ?Sized,
+    {
+        self.closest(target, Ordering::Greater, true)
+    }
+
+    /// Returns the largest value strictly smaller than `target`.
+    ///
+    /// Unlike `inorder_predecessor`, which removes the largest value of a
+    /// sub-tree, this leaves the tree untouched and `target` does not need to
+    /// belong to the tree.
+    pub fn predecessor<Q>(&self, target: &Q) -> Option<&T>
+    where
+        T: Borrow<Q>,
+        C: Compare<Q>,
+        Q: ?Sized,
+    {
+        self.closest(target, Ordering::Less, false)
+    }
+
+    /// Returns the smallest value strictly larger than `target`.
+    pub fn successor<Q>(&self, target: &Q) -> Option<&T>
+    where
+        T: Borrow<Q>,
+        C: Compare<Q>,
+        Q: ?Sized,
+    {
+        self.closest(target, Ordering::Greater, false)
+    }
+
+    /// Returns the value closest to `target` on the `side` of it, where `side`
+    /// is `Ordering::Less` or `Ordering::Greater`. `target` itself is a
+    /// candidate iff `inclusive`.
+    fn closest<Q>(&self, target: &Q, side: Ordering, inclusive: bool) -> Option<&T>
+    where
+        T: Borrow<Q>,
+        C: Compare<Q>,
+        Q: ?Sized,
+    {
+        let mut best = None;
+        let mut current = self;
+        while let Some(ref n) = current.0 {
+            let ordering = C::compare(n.value.borrow(), target);
+            if ordering == Ordering::Equal && inclusive {
+                return Some(&n.value);
+            }
+            if ordering == side {
+                best = Some(&n.value);
+            }
+
+            // Move towards `target`, or away from `side` when it was found
+            current = match ordering {
+                Ordering::Less => &n.right,
+                Ordering::Greater => &n.left,
+                Ordering::Equal if side == Ordering::Less => &n.left,
+                Ordering::Equal => &n.right,
+            };
+        }
+
+        best
+    }
+
+    /// Returns the number of values of the tree strictly smaller than `target`.
+    ///
+    /// Nodes do not store the size of their sub-tree, so this walks the values
+    /// in order and takes O(rank + height) time.
+    pub fn rank<Q>(&self, target: &Q) -> usize
+    where
+        T: Borrow<Q>,
+        C: Compare<Q>,
+        Q: ?Sized,
+    {
+        self.iter()
+            .take_while(|v| C::compare((*v).borrow(), target) == Ordering::Less)
+            .count()
+    }
+
+    /// Returns the `k`-th smallest value of the tree, starting from 0.
+    /// This is the inverse of `rank`: `select(rank(v)) == Some(v)` for every
+    /// value `v` of the tree.
+    ///
+    /// Like `rank`, this walks the values in order and takes O(k + height)
+    /// time, no better than `iter().nth(k)`.
+    pub fn select(&self, k: usize) -> Option<&T> {
+        self.iter().nth(k)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::test::tree;
+
+    #[test]
+    fn finds_extrema() {
+        let bst = tree(&[15, 10, 20, 8, 12, 18, 30]);
+        assert_eq!(bst.min(), Some(&8));
+        assert_eq!(bst.max(), Some(&30));
+        assert_eq!(Tree::<i32>::new().min(), None);
+        assert_eq!(Tree::<i32>::new().max(), None);
+    }
+
+    #[test]
+    fn finds_floor_and_ceiling() {
+        let bst = tree(&[15, 10, 20, 8, 12, 18, 30]);
+        assert_eq!(bst.floor(&12), Some(&12));
+        assert_eq!(bst.floor(&14), Some(&12));
+        assert_eq!(bst.floor(&7), None);
+        assert_eq!(bst.ceiling(&12), Some(&12));
+        assert_eq!(bst.ceiling(&13), Some(&15));
+        assert_eq!(bst.ceiling(&31), None);
+    }
+
+    #[test]
+    fn finds_predecessor_and_successor() {
+        let bst = tree(&[15, 10, 20, 8, 12, 18, 30]);
+        assert_eq!(bst.predecessor(&15), Some(&12));
+        assert_eq!(bst.predecessor(&18), Some(&15));
+        assert_eq!(bst.predecessor(&8), None);
+        assert_eq!(bst.successor(&15), Some(&18));
+        assert_eq!(bst.successor(&12), Some(&15));
+        assert_eq!(bst.successor(&19), Some(&20));
+        assert_eq!(bst.successor(&30), None);
+    }
+
+    #[test]
+    fn ranks_and_selects() {
+        let bst = tree(&[15, 10, 20, 8, 12, 18, 30]);
+        assert_eq!(bst.rank(&8), 0);
+        assert_eq!(bst.rank(&16), 4);
+        assert_eq!(bst.rank(&100), 7);
+        assert_eq!(bst.select(0), Some(&8));
+        assert_eq!(bst.select(6), Some(&30));
+        assert_eq!(bst.select(7), None);
+        for v in bst.iter() {
+            assert_eq!(bst.select(bst.rank(v)), Some(v));
+        }
+    }
+}
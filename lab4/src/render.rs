@@ -0,0 +1,219 @@
+use crate::{Node, Tree};
+use std::fmt::{Debug, Write};
+
+/// Position of a node relative to its parent, which selects its connector.
+#[derive(Clone, Copy, PartialEq)]
+enum Side {
+    Root,
+    Left,
+    Right,
+}
+
+impl<T, C> Tree<T, C>
+where
+    T: Debug,
+{
+    /// Renders the tree sideways, one value per line, like the `print`
+    /// helper of `alternate/main.rs`: the root is on the left, right
+    /// sub-trees are drawn above their parent and left sub-trees below.
+    /// Values counted several times (see `Duplicates::Count`) are followed by
+    /// their multiplicity, e.g. `18 (x2)`.
+    ///
+    /// ```text
+    ///         ┌── 30
+    ///     ┌── 20
+    ///     │   └── 18
+    /// 15
+    ///     └── 10
+    ///         └── 8
+    /// ```
+    pub fn render(&self) -> String {
+        enum Step<'a, T, C> {
+            Visit(&'a Node<T, C>, String, Side),
+            Emit(&'a Node<T, C>, String, Side),
+        }
+
+        let mut out = String::new();
+        let mut stack: Vec<Step<T, C>> = self
+            .0
+            .as_deref()
+            .map(|n| Step::Visit(n, String::new(), Side::Root))
+            .into_iter()
+            .collect();
+
+        // Reverse in-order traversal: right sub-tree, node, left sub-tree,
+        // pushed in the opposite order on the stack
+        while let Some(step) = stack.pop() {
+            match step {
+                Step::Visit(n, prefix, side) => {
+                    if let Some(ref left) = n.left.0 {
+                        let bar = if side == Side::Right {
+                            "│   "
+                        } else {
+                            "    "
+                        };
+                        stack.push(Step::Visit(
+                            left,
+                            child_prefix(&prefix, side, bar),
+                            Side::Left,
+                        ));
+                    }
+                    stack.push(Step::Emit(n, prefix.clone(), side));
+                    if let Some(ref right) = n.right.0 {
+                        let bar = if side == Side::Left { "│   " } else { "    " };
+                        stack.push(Step::Visit(
+                            right,
+                            child_prefix(&prefix, side, bar),
+                            Side::Right,
+                        ));
+                    }
+                }
+                Step::Emit(n, prefix, side) => {
+                    let connector = match side {
+                        Side::Root => "",
+                        Side::Left => "└── ",
+                        Side::Right => "┌── ",
+                    };
+                    write!(out, "{}{}{:?}", prefix, connector, n.value).unwrap();
+                    if n.count > 1 {
+                        write!(out, " (x{})", n.count).unwrap();
+                    }
+                    out.push('\n');
+                }
+            }
+        }
+
+        out
+    }
+
+    /// Returns a Graphviz DOT description of the tree, labelling nodes with
+    /// the `Debug` representation of their value.
+    ///
+    /// When a node has a single child, an invisible node stands for the missing
+    /// one so that `dot` still draws the child on the correct side, and
+    /// `ordering=out` keeps the children in left to right order. The output
+    /// can be rendered with e.g. `dot -Tsvg tree.dot -o tree.svg`.
+    pub fn to_dot(&self) -> String {
+        let mut out =
+            String::from("digraph Tree {\n    node [shape=circle];\n    graph [ordering=out];\n");
+        let mut next_id = 0;
+        let mut stack = Vec::new();
+        if let Some(ref n) = self.0 {
+            stack.push((n.as_ref(), next_id));
+            next_id += 1;
+        }
+
+        while let Some((n, id)) = stack.pop() {
+            let label = format!("{:?}", n.value)
+                .replace('\\', "\\\\")
+                .replace('"', "\\\"");
+            writeln!(out, "    n{} [label=\"{}\"];", id, label).unwrap();
+            if n.left.0.is_none() && n.right.0.is_none() {
+                continue;
+            }
+
+            // Children are popped in reverse order: push right first
+            let mut children = Vec::with_capacity(2);
+            for child in [&n.left, &n.right] {
+                match child.0 {
+                    Some(ref c) => {
+                        writeln!(out, "    n{} -> n{};", id, next_id).unwrap();
+                        children.push((c.as_ref(), next_id));
+                    }
+                    None => {
+                        writeln!(out, "    n{} [label=\"\", style=invis];", next_id).unwrap();
+                        writeln!(out, "    n{} -> n{} [style=invis];", id, next_id).unwrap();
+                    }
+                }
+                next_id += 1;
+            }
+            stack.extend(children.into_iter().rev());
+        }
+
+        out.push_str("}\n");
+        out
+    }
+}
+
+/// Returns the prefix of the children of a node with the given `prefix`:
+/// `bar` continues the branch of the node when it has to reach its sibling.
+fn child_prefix(prefix: &str, side: Side, bar: &str) -> String {
+    if side == Side::Root {
+        String::from("    ")
+    } else {
+        format!("{}{}", prefix, bar)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn renders() {
+        let mut bst = Tree::new();
+        for v in [15, 10, 20, 8, 12, 18, 30, 19] {
+            bst.checked(|t| t.insert(v)).expect("Failed to insert");
+        }
+
+        let expected = "        ┌── 30
+    ┌── 20
+    │   │   ┌── 19
+    │   └── 18
+15
+    │   ┌── 12
+    └── 10
+        └── 8
+";
+        assert_eq!(bst.render(), expected);
+        assert_eq!(Tree::<i32>::new().render(), "");
+    }
+
+    #[test]
+    fn renders_multiplicities() {
+        let mut bst = Tree::new();
+        for v in [15, 10, 15, 15] {
+            bst.checked(|t| t.insert_with(v, crate::Duplicates::Count))
+                .expect("Failed to insert");
+        }
+        assert_eq!(bst.render(), "15 (x3)\n    └── 10\n");
+    }
+
+    #[test]
+    fn exports_dot() {
+        let mut bst = Tree::new();
+        for v in [15, 10, 20, 18] {
+            bst.checked(|t| t.insert(v)).expect("Failed to insert");
+        }
+
+        let expected = r#"digraph Tree {
+    node [shape=circle];
+    graph [ordering=out];
+    n0 [label="15"];
+    n0 -> n1;
+    n0 -> n2;
+    n1 [label="10"];
+    n2 [label="20"];
+    n2 -> n3;
+    n4 [label="", style=invis];
+    n2 -> n4 [style=invis];
+    n3 [label="18"];
+}
+"#;
+        assert_eq!(bst.to_dot(), expected);
+        assert_eq!(
+            Tree::<i32>::new().to_dot(),
+            "digraph Tree {\n    node [shape=circle];\n    graph [ordering=out];\n}\n"
+        );
+    }
+
+    #[test]
+    fn escapes_dot_labels() {
+        let mut bst = Tree::new();
+        bst.checked(|t| t.insert("a \"quoted\" value"))
+            .expect("Failed to insert");
+        assert!(bst
+            .to_dot()
+            .contains(r#"n0 [label="\"a \\\"quoted\\\" value\""];"#));
+    }
+}
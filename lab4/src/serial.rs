@@ -0,0 +1,530 @@
+use crate::{Compare, Node, Tree};
+use std::convert::TryInto;
+use std::error::Error;
+use std::fmt::{self, Display};
+use std::marker::PhantomData;
+use std::str::FromStr;
+
+/// Header of the binary format, followed by the version of the format.
+const MAGIC: &[u8; 4] = b"BST\0";
+const VERSION: u8 = 1;
+
+/// Binary tags of the pre-order sequence.
+const EMPTY: u8 = 0;
+const LEAF: u8 = 1;
+const COUNTED: u8 = 2;
+
+/// Empty sub-tree marker of the text format.
+const EMPTY_LINE: &str = ".";
+
+/// Errors returned when decoding a serialized `Tree`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DecodeError {
+    /// The binary header is missing or of an unknown version.
+    BadHeader,
+    /// The input ended before the tree was complete.
+    UnexpectedEnd,
+    /// Data remains after a complete tree.
+    TrailingData,
+    /// A node of the text format could not be parsed, at the given line
+    /// (from 1).
+    InvalidLine(usize),
+    /// A node of the binary format could not be parsed, at the given byte
+    /// offset (from 0).
+    InvalidByte(usize),
+    /// The decoded values are not in strictly increasing order, or a
+    /// multiplicity is 0 (see `Tree::validate`).
+    Unordered,
+}
+
+impl Display for DecodeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DecodeError::BadHeader => write!(f, "missing or unsupported header"),
+            DecodeError::UnexpectedEnd => write!(f, "input ends before the tree is complete"),
+            DecodeError::TrailingData => write!(f, "data remains after the tree"),
+            DecodeError::InvalidLine(line) => write!(f, "invalid node at line {}", line),
+            DecodeError::InvalidByte(offset) => {
+                write!(f, "invalid node at byte offset {}", offset)
+            }
+            DecodeError::Unordered => write!(f, "values are not strictly increasing"),
+        }
+    }
+}
+
+impl Error for DecodeError {}
+
+/// Compact binary encoding of the values of a `Tree`.
+///
+/// Integers are encoded as LEB128 variable-length integers (zigzag encoded
+/// when signed), so that small values take a single byte.
+pub trait Encode: Sized {
+    /// Appends the encoding of `self` to `out`.
+    fn encode(&self, out: &mut Vec<u8>);
+
+    /// Decodes a value from the start of `input` and advances `input` past
+    /// it. Returns `None` if `input` does not start with a valid encoding.
+    fn decode(input: &mut &[u8]) -> Option<Self>;
+}
+
+fn encode_varint(mut v: u64, out: &mut Vec<u8>) {
+    while v >= 0x80 {
+        out.push(v as u8 | 0x80);
+        v >>= 7;
+    }
+    out.push(v as u8);
+}
+
+fn decode_varint(input: &mut &[u8]) -> Option<u64> {
+    let mut v = 0u64;
+    for shift in (0..64).step_by(7) {
+        let (&byte, rest) = input.split_first()?;
+        *input = rest;
+        // The 10th byte only holds the highest bit of a u64
+        let bits = u64::from(byte & 0x7f);
+        if (bits << shift) >> shift != bits {
+            return None;
+        }
+        v |= bits << shift;
+        if byte & 0x80 == 0 {
+            return Some(v);
+        }
+    }
+    None
+}
+
+macro_rules! encode_unsigned {
+    ($($t:ty),*) => {$(
+        impl Encode for $t {
+            fn encode(&self, out: &mut Vec<u8>) {
+                encode_varint(*self as u64, out);
+            }
+
+            fn decode(input: &mut &[u8]) -> Option<Self> {
+                decode_varint(input)?.try_into().ok()
+            }
+        }
+    )*};
+}
+
+macro_rules! encode_signed {
+    ($($t:ty),*) => {$(
+        impl Encode for $t {
+            fn encode(&self, out: &mut Vec<u8>) {
+                let v = *self as i64;
+                encode_varint(((v << 1) ^ (v >> 63)) as u64, out);
+            }
+
+            fn decode(input: &mut &[u8]) -> Option<Self> {
+                let v = decode_varint(input)?;
+                let v = (v >> 1) as i64 ^ -((v & 1) as i64);
+                v.try_into().ok()
+            }
+        }
+    )*};
+}
+
+encode_unsigned!(u8, u16, u32, u64, usize);
+encode_signed!(i8, i16, i32, i64, isize);
+
+impl Encode for bool {
+    fn encode(&self, out: &mut Vec<u8>) {
+        out.push(*self as u8);
+    }
+
+    fn decode(input: &mut &[u8]) -> Option<Self> {
+        match u8::decode(input)? {
+            0 => Some(false),
+            1 => Some(true),
+            _ => None,
+        }
+    }
+}
+
+impl Encode for char {
+    fn encode(&self, out: &mut Vec<u8>) {
+        u32::from(*self).encode(out);
+    }
+
+    fn decode(input: &mut &[u8]) -> Option<Self> {
+        char::from_u32(u32::decode(input)?)
+    }
+}
+
+/// Strings are encoded as their length in bytes followed by their UTF-8 bytes.
+impl Encode for String {
+    fn encode(&self, out: &mut Vec<u8>) {
+        self.len().encode(out);
+        out.extend_from_slice(self.as_bytes());
+    }
+
+    fn decode(input: &mut &[u8]) -> Option<Self> {
+        let len = usize::decode(input)?;
+        if input.len() < len {
+            return None;
+        }
+        let (bytes, rest) = input.split_at(len);
+        *input = rest;
+        String::from_utf8(bytes.to_vec()).ok()
+    }
+}
+
+/// Serialization of `Tree`s, preserving their shape.
+///
+/// Both formats list the nodes in pre-order, with a marker for each empty
+/// sub-tree, so that decoding rebuilds exactly the same tree in O(n) without
+/// comparing values. Encoding and decoding are iterative and handle
+/// degenerate trees.
+///
+/// The text format has one line per node or empty sub-tree:
+///
+/// ```text
+/// 15
+/// 10
+/// .
+/// .
+/// *2 20
+/// .
+/// .
+/// ```
+///
+/// `.` marks an empty sub-tree, other lines hold the `Display` form of a value,
+/// prefixed by `*count ` when it occurs several times (see `Duplicates::Count`).
+/// Backslashes, newlines and leading `.` and `*` are escaped with a backslash.
+///
+/// The binary format starts with `BST\0` and a version byte, then holds a tag
+/// for each node or empty sub-tree: 0 for an empty sub-tree, 1 for a value
+/// occurring once and 2 for a value followed by its multiplicity as a varint.
+/// Values are written with `Encode`.
+impl<T, C> Tree<T, C>
+where
+    C: Compare<T>,
+{
+    /// Serializes the tree to the text format.
+    pub fn to_text(&self) -> String
+    where
+        T: Display,
+    {
+        let mut out = String::new();
+        for node in self.pre_order_nodes() {
+            match node {
+                None => out.push_str(EMPTY_LINE),
+                Some(n) => {
+                    if n.count > 1 {
+                        out.push_str(&format!("*{} ", n.count));
+                    }
+                    out.push_str(&escape(&n.value.to_string()));
+                }
+            }
+            out.push('\n');
+        }
+
+        out
+    }
+
+    /// Rebuilds a tree from the text format.
+    pub fn from_text(text: &str) -> Result<Self, DecodeError>
+    where
+        T: FromStr,
+    {
+        let mut lines = text.lines().enumerate();
+        let tree = Self::decode_pre_order(|| {
+            let (i, line) = lines.next().ok_or(DecodeError::UnexpectedEnd)?;
+            if line == EMPTY_LINE {
+                return Ok(None);
+            }
+
+            let invalid = DecodeError::InvalidLine(i + 1);
+            let (count, value) = match line.strip_prefix('*') {
+                Some(counted) => {
+                    let (count, value) = counted.split_once(' ').ok_or(invalid)?;
+                    (count.parse().map_err(|_| invalid)?, value)
+                }
+                None => (1, line),
+            };
+            let value = unescape(value).ok_or(invalid)?;
+            match value.parse() {
+                Ok(value) if count > 0 => Ok(Some((value, count))),
+                _ => Err(invalid),
+            }
+        })?;
+
+        match lines.next() {
+            None => Ok(tree),
+            Some(_) => Err(DecodeError::TrailingData),
+        }
+    }
+
+    /// Serializes the tree to the binary format.
+    pub fn to_bytes(&self) -> Vec<u8>
+    where
+        T: Encode,
+    {
+        let mut out = MAGIC.to_vec();
+        out.push(VERSION);
+        for node in self.pre_order_nodes() {
+            match node {
+                None => out.push(EMPTY),
+                Some(n) if n.count == 1 => {
+                    out.push(LEAF);
+                    n.value.encode(&mut out);
+                }
+                Some(n) => {
+                    out.push(COUNTED);
+                    n.value.encode(&mut out);
+                    n.count.encode(&mut out);
+                }
+            }
+        }
+
+        out
+    }
+
+    /// Rebuilds a tree from the binary format.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, DecodeError>
+    where
+        T: Encode,
+    {
+        let mut input = bytes
+            .strip_prefix(&MAGIC[..])
+            .and_then(|rest| rest.strip_prefix(&[VERSION]))
+            .ok_or(DecodeError::BadHeader)?;
+
+        let tree = Self::decode_pre_order(|| {
+            let offset = bytes.len() - input.len();
+            let invalid = DecodeError::InvalidByte(offset);
+            let (&tag, rest) = input.split_first().ok_or(DecodeError::UnexpectedEnd)?;
+            input = rest;
+
+            let value = match tag {
+                EMPTY => return Ok(None),
+                LEAF | COUNTED => T::decode(&mut input).ok_or(invalid)?,
+                _ => return Err(invalid),
+            };
+            let count = match tag {
+                COUNTED => usize::decode(&mut input)
+                    .filter(|&c| c > 0)
+                    .ok_or(invalid)?,
+                _ => 1,
+            };
+            Ok(Some((value, count)))
+        })?;
+
+        if input.is_empty() {
+            Ok(tree)
+        } else {
+            Err(DecodeError::TrailingData)
+        }
+    }
+
+    /// Returns the nodes of the tree in pre-order, with `None` for each
+    /// empty sub-tree.
+    fn pre_order_nodes(&self) -> Vec<Option<&Node<T, C>>> {
+        let mut nodes = Vec::new();
+        let mut stack = vec![self];
+        while let Some(t) = stack.pop() {
+            nodes.push(t.0.as_deref());
+            if let Some(ref n) = t.0 {
+                stack.push(&n.right);
+                stack.push(&n.left);
+            }
+        }
+        nodes
+    }
+
+    /// Rebuilds a tree from its pre-order sequence, read with `next`, then
+    /// validates it.
+    fn decode_pre_order<F>(mut next: F) -> Result<Self, DecodeError>
+    where
+        F: FnMut() -> Result<Option<(T, usize)>, DecodeError>,
+    {
+        // Read the whole tree first: the number of empty sub-trees exceeds
+        // the number of nodes exactly once the sequence is complete
+        let mut nodes = Vec::new();
+        let mut missing = 1;
+        while missing > 0 {
+            let node = next()?;
+            missing += if node.is_some() { 1 } else { -1 };
+            nodes.push(node);
+        }
+
+        // In reverse pre-order, both sub-trees of a node are built before it,
+        // the left one last
+        let mut stack: Vec<Tree<T, C>> = Vec::new();
+        for node in nodes.into_iter().rev() {
+            let tree = match node {
+                None => Tree(None),
+                Some((value, count)) => {
+                    let left = stack.pop().ok_or(DecodeError::UnexpectedEnd)?;
+                    let right = stack.pop().ok_or(DecodeError::UnexpectedEnd)?;
+                    Tree(Some(Box::new(Node {
+                        value,
+                        count,
+                        left,
+                        right,
+                        order: PhantomData,
+                    })))
+                }
+            };
+            stack.push(tree);
+        }
+
+        let tree = stack.pop().ok_or(DecodeError::UnexpectedEnd)?;
+        tree.validate().map_err(|_| DecodeError::Unordered)?;
+        Ok(tree)
+    }
+}
+
+/// Escapes `value` so that it fits on a line of the text format.
+fn escape(value: &str) -> String {
+    let mut out = String::with_capacity(value.len());
+    if value.starts_with('.') || value.starts_with('*') {
+        out.push('\\');
+    }
+    for c in value.chars() {
+        match c {
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            c => out.push(c),
+        }
+    }
+    out
+}
+
+/// Reverts `escape`, returns `None` on an invalid escape sequence.
+fn unescape(line: &str) -> Option<String> {
+    let mut out = String::with_capacity(line.len());
+    let mut chars = line.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            out.push(c);
+            continue;
+        }
+        out.push(match chars.next()? {
+            '\\' => '\\',
+            'n' => '\n',
+            'r' => '\r',
+            c @ ('.' | '*') if out.is_empty() => c,
+            _ => return None,
+        });
+    }
+    Some(out)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::test::tree;
+    use crate::Duplicates;
+
+    #[test]
+    fn round_trips_text() {
+        // With a negative value and a counted one
+        let mut bst = tree(&[15, 10, 20, 8, 12, -18, 30]);
+        bst.checked(|t| t.insert_with(30, Duplicates::Count))
+            .expect("Failed to insert");
+        let text = bst.to_text();
+        assert!(text.starts_with("15\n10\n8\n-18\n.\n.\n.\n12\n.\n.\n20\n"));
+        assert!(text.contains("*2 30\n"));
+        assert_eq!(Tree::from_text(&text), Ok(bst));
+
+        let empty = Tree::<i32>::new();
+        assert_eq!(empty.to_text(), ".\n");
+        assert_eq!(Tree::from_text(&empty.to_text()), Ok(empty));
+    }
+
+    #[test]
+    fn round_trips_bytes() {
+        // With a negative value and a counted one
+        let mut bst = tree(&[15, 10, 20, 8, 12, -18, 30]);
+        bst.checked(|t| t.insert_with(30, Duplicates::Count))
+            .expect("Failed to insert");
+        let bytes = bst.to_bytes();
+        // Header, then tags and one-byte values: 15 is zigzag encoded as 30
+        assert_eq!(&bytes[..7], b"BST\0\x01\x01\x1e");
+        assert_eq!(Tree::from_bytes(&bytes), Ok(bst));
+    }
+
+    #[test]
+    fn escapes_text_values() {
+        let mut bst = Tree::new();
+        for s in [".", "*2 x", "a\nb", "back\\slash", ""] {
+            bst.checked(|t| t.insert(String::from(s)))
+                .expect("Failed to insert");
+        }
+        let text = bst.to_text();
+        assert_eq!(text.lines().count(), 11);
+        assert_eq!(Tree::from_text(&text), Ok(bst));
+    }
+
+    #[test]
+    fn round_trips_strings() {
+        let mut bst = Tree::new();
+        for s in ["kiwi", "apple", "fig", "été"] {
+            bst.checked(|t| t.insert(String::from(s)))
+                .expect("Failed to insert");
+        }
+        assert_eq!(Tree::from_bytes(&bst.to_bytes()), Ok(bst));
+    }
+
+    #[test]
+    fn round_trips_degenerate_trees() {
+        let bst = crate::test::sorted_chain(100_000);
+        assert_eq!(Tree::from_text(&bst.to_text()).as_ref(), Ok(&bst));
+        assert_eq!(Tree::from_bytes(&bst.to_bytes()), Ok(bst));
+    }
+
+    #[test]
+    fn rejects_invalid_input() {
+        type T = Tree<i32>;
+        assert_eq!(T::from_text("15\n.\n"), Err(DecodeError::UnexpectedEnd));
+        assert_eq!(T::from_text(".\n.\n"), Err(DecodeError::TrailingData));
+        assert_eq!(T::from_text("1\nx\n"), Err(DecodeError::InvalidLine(2)));
+        assert_eq!(
+            T::from_text("*0 1\n.\n.\n"),
+            Err(DecodeError::InvalidLine(1))
+        );
+        assert_eq!(T::from_text("1\n2\n.\n.\n.\n"), Err(DecodeError::Unordered));
+
+        assert_eq!(T::from_bytes(b"BST"), Err(DecodeError::BadHeader));
+        assert_eq!(
+            T::from_bytes(b"BST\0\x01\x01"),
+            Err(DecodeError::InvalidByte(5))
+        );
+        assert_eq!(
+            T::from_bytes(b"BST\0\x01\x07"),
+            Err(DecodeError::InvalidByte(5))
+        );
+        assert_eq!(
+            T::from_bytes(b"BST\0\x01\x00\x00"),
+            Err(DecodeError::TrailingData)
+        );
+        assert_eq!(
+            T::from_text("1\nx\n").unwrap_err().to_string(),
+            "invalid node at line 2"
+        );
+        assert_eq!(
+            T::from_bytes(b"BST\0\x01\x07").unwrap_err().to_string(),
+            "invalid node at byte offset 5"
+        );
+    }
+
+    #[test]
+    fn rejects_overlong_varints() {
+        let mut max = Vec::new();
+        u64::MAX.encode(&mut max);
+        assert_eq!(max, b"\xff\xff\xff\xff\xff\xff\xff\xff\xff\x01");
+        assert_eq!(u64::decode(&mut &max[..]), Some(u64::MAX));
+
+        // Bits beyond the 64th in the 10th byte
+        let overlong = b"\xff\xff\xff\xff\xff\xff\xff\xff\xff\x02";
+        assert_eq!(u64::decode(&mut &overlong[..]), None);
+        let mut bytes = b"BST\0\x01\x01".to_vec();
+        bytes.extend_from_slice(overlong);
+        assert_eq!(
+            Tree::<u64>::from_bytes(&bytes),
+            Err(DecodeError::InvalidByte(5))
+        );
+    }
+}
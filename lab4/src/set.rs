@@ -0,0 +1,217 @@
+use crate::{Compare, Tree};
+use std::borrow::Borrow;
+use std::cmp::Ordering;
+
+/// Set operations on `Tree`s.
+///
+/// The binary operations consume both trees: their values are merged in a
+/// single pass over the two in-order sequences and the result is rebuilt as a
+/// perfectly balanced tree, in O(n + m) instead of re-inserting every value.
+/// Multiplicities (see `Duplicates::Count`) are not kept by these operations.
+impl<T, C> Tree<T, C>
+where
+    C: Compare<T>,
+{
+    /// Returns the values belonging to `self` or `other`.
+    pub fn union(self, other: Self) -> Self {
+        merge(self, other, |_, _| true)
+    }
+
+    /// Returns the values belonging to both `self` and `other`.
+    pub fn intersection(self, other: Self) -> Self {
+        merge(self, other, |in_self, in_other| in_self && in_other)
+    }
+
+    /// Returns the values of `self` that do not belong to `other`.
+    pub fn difference(self, other: Self) -> Self {
+        merge(self, other, |in_self, in_other| in_self && !in_other)
+    }
+
+    /// Returns the values belonging to exactly one of `self` and `other`.
+    pub fn symmetric_difference(self, other: Self) -> Self {
+        merge(self, other, |in_self, in_other| in_self != in_other)
+    }
+
+    /// Splits the tree around `key`: returns the tree of values smaller than
+    /// `key`, `key` itself if it belonged to the tree, and the tree of values
+    /// larger than `key`.
+    ///
+    /// Only the nodes on the path to `key` are visited and relinked, the
+    /// sub-trees hanging off that path are moved as a whole.
+    pub fn split<Q>(mut self, key: &Q) -> (Self, Option<T>, Self)
+    where
+        T: Borrow<Q>,
+        C: Compare<Q>,
+        Q: ?Sized,
+    {
+        let mut less = Tree(None);
+        let mut greater = Tree(None);
+        // Empty slots where the next sub-tree of each side must be grafted:
+        // nodes added to `less` are larger than all its previous values, so
+        // they go down its right spine, and conversely for `greater`.
+        let mut less_slot = &mut less;
+        let mut greater_slot = &mut greater;
+        let mut found = None;
+
+        let mut current = self.0.take();
+        while let Some(mut n) = current {
+            match C::compare(n.value.borrow(), key) {
+                Ordering::Less => {
+                    current = n.right.0.take();
+                    less_slot.0 = Some(n);
+                    less_slot = &mut less_slot.0.as_mut().unwrap().right;
+                }
+                Ordering::Greater => {
+                    current = n.left.0.take();
+                    greater_slot.0 = Some(n);
+                    greater_slot = &mut greater_slot.0.as_mut().unwrap().left;
+                }
+                Ordering::Equal => {
+                    less_slot.0 = n.left.0.take();
+                    greater_slot.0 = n.right.0.take();
+                    found = Some(n.value);
+                    break;
+                }
+            }
+        }
+
+        (less, found, greater)
+    }
+
+    /// Appends `other` to `self`, where every value of `other` is larger than
+    /// every value of `self`. This is the inverse of `split`.
+    ///
+    /// Like `add_to_end`, `other` is grafted as a whole in the only empty slot
+    /// that can hold it: the right child of the largest value of `self`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the values of `other` are not all larger than those of `self`.
+    pub fn join(&mut self, other: Self) {
+        if let (Some(max), Some(min)) = (self.max(), other.min()) {
+            assert!(
+                C::compare(max, min) == Ordering::Less,
+                "joined trees overlap"
+            );
+        }
+
+        let mut slot = self;
+        while let Some(ref mut n) = slot.0 {
+            slot = &mut n.right;
+        }
+        *slot = other;
+    }
+}
+
+/// Merges the in-order sequences of `a` and `b`, keeping the values for which
+/// `keep(in_a, in_b)` holds.
+fn merge<T, C, F>(a: Tree<T, C>, b: Tree<T, C>, keep: F) -> Tree<T, C>
+where
+    C: Compare<T>,
+    F: Fn(bool, bool) -> bool,
+{
+    let mut a = a.into_iter().peekable();
+    let mut b = b.into_iter().peekable();
+    let mut values = Vec::new();
+
+    loop {
+        let ordering = match (a.peek(), b.peek()) {
+            (Some(x), Some(y)) => C::compare(x, y),
+            (Some(_), None) => Ordering::Less,
+            (None, Some(_)) => Ordering::Greater,
+            (None, None) => break,
+        };
+        let (value, in_a, in_b) = match ordering {
+            Ordering::Less => (a.next().unwrap(), true, false),
+            Ordering::Greater => (b.next().unwrap(), false, true),
+            Ordering::Equal => {
+                b.next();
+                (a.next().unwrap(), true, true)
+            }
+        };
+
+        if keep(in_a, in_b) {
+            values.push(value);
+        }
+    }
+
+    let len = values.len();
+    Tree::build_balanced(&mut values.into_iter(), len)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::test::tree;
+
+    /// Returns the values of `bst`, after checking that it is valid.
+    fn values(bst: &Tree<i32>) -> Vec<i32> {
+        assert_eq!(bst.validate(), Ok(()));
+        bst.iter().copied().collect()
+    }
+
+    #[test]
+    fn unites() {
+        let bst = tree(&[5, 1, 3, 7]).union(tree(&[2, 3, 8]));
+        assert_eq!(values(&bst), vec![1, 2, 3, 5, 7, 8]);
+        assert_eq!(values(&Tree::new().union(tree(&[1]))), vec![1]);
+    }
+
+    #[test]
+    fn intersects() {
+        let bst = tree(&[5, 1, 3, 7]).intersection(tree(&[2, 3, 7, 8]));
+        assert_eq!(values(&bst), vec![3, 7]);
+        assert_eq!(values(&tree(&[1]).intersection(Tree::new())), vec![]);
+    }
+
+    #[test]
+    fn differences() {
+        let bst = tree(&[5, 1, 3, 7]).difference(tree(&[2, 3, 7, 8]));
+        assert_eq!(values(&bst), vec![1, 5]);
+
+        let bst = tree(&[5, 1, 3, 7]).symmetric_difference(tree(&[2, 3, 7, 8]));
+        assert_eq!(values(&bst), vec![1, 2, 5, 8]);
+    }
+
+    #[test]
+    fn balances_results() {
+        let evens = (0..1000).step_by(2).collect::<Vec<_>>();
+        let odds = (1..1000).step_by(2).collect::<Vec<_>>();
+        // Sorted insertions: both operands are degenerate
+        let bst = tree(&evens).union(tree(&odds));
+
+        assert!(bst.iter().copied().eq(0..1000));
+        // A perfectly balanced tree of 1000 values has 10 levels
+        assert_eq!(bst.height(), 10);
+    }
+
+    #[test]
+    fn splits() {
+        let (less, found, greater) = tree(&[15, 10, 20, 8, 12, 18, 30]).split(&15);
+        assert_eq!(values(&less), vec![8, 10, 12]);
+        assert_eq!(found, Some(15));
+        assert_eq!(values(&greater), vec![18, 20, 30]);
+
+        let (less, found, greater) = tree(&[15, 10, 20, 8, 12, 18, 30]).split(&11);
+        assert_eq!(values(&less), vec![8, 10]);
+        assert_eq!(found, None);
+        assert_eq!(values(&greater), vec![12, 15, 18, 20, 30]);
+    }
+
+    #[test]
+    fn joins() {
+        let (mut less, _, greater) = tree(&[15, 10, 20, 8, 12, 18, 30]).split(&12);
+        less.checked(|t| t.join(greater));
+        assert_eq!(values(&less), vec![8, 10, 15, 18, 20, 30]);
+
+        let mut empty = Tree::new();
+        empty.checked(|t| t.join(tree(&[1, 2])));
+        assert_eq!(values(&empty), vec![1, 2]);
+    }
+
+    #[test]
+    #[should_panic(expected = "joined trees overlap")]
+    fn refuses_overlapping_joins() {
+        tree(&[1, 5]).join(tree(&[3, 7]));
+    }
+}
@@ -1,7 +1,7 @@
 #!/bin/sh
 
 rm -f target/out.dat
-if [ $# -eq 1 ]; then
+if [ $# -ge 1 ]; then
     if [ $1 -lt 2 ]; then
         echo "error: need at least 2 runs to compute statistics"
         exit 1
@@ -11,6 +11,31 @@ if [ $# -eq 1 ]; then
 else
     runs=5
 fi
+# Optionally record the results as the new baseline, or compare against it,
+# and record the hardware counters alongside the timings
+baseline=target/baseline.json
+opts=""
+with_baseline=""
+if [ $# -ge 1 ]; then
+    shift
+fi
+for mode in "$@"; do
+    case "$mode" in
+        save)
+            opts="$opts --save $baseline"
+            with_baseline=1
+            ;;
+        compare)
+            opts="$opts --compare $baseline"
+            with_baseline=1
+            ;;
+        perf) opts="$opts --perf" ;;
+        *)
+            echo "error: unknown mode '$mode', expected 'save', 'compare' or 'perf'"
+            exit 1
+            ;;
+    esac
+done
 sizes=(256 512 768 1024 1280 1536 1792)
 target=target/release/matrix_multiplication
 
@@ -22,7 +47,7 @@ printf "     \033[1;32mRunning\033[0m release [optimized] $runs runs per matrix
 before=$(date +%s.%N)
 for size in "${sizes[@]}"; do
     printf "Matrix %zux%zu...\t" "$size" "$size"
-    taskset -c 7 $target $size $runs | grep -e "[0-9]" >> target/out.dat
+    taskset -c 7 $target $size $runs $opts | grep -e "^[0-9]" >> target/out.dat
     echo "done"
 done
 after=$(date +%s.%N)
@@ -33,5 +58,8 @@ printf "\nBenchmarks finished in %.2f seconds\n" "$elapsed"
 gnuplot bench.gp
 printf "Data: \'target/out.dat\'\n"
 printf "Plot: \'target/bench.png\'\n"
+if [ -n "$with_baseline" ]; then
+    printf "Baseline: \'$baseline\'\n"
+fi
 
 exit 0
@@ -0,0 +1,162 @@
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+/// Two-sided 95% critical values of Student's t distribution, indexed by the
+/// degrees of freedom minus one.
+const T_CRITICAL: [f64; 30] = [
+    12.706, 4.303, 3.182, 2.776, 2.571, 2.447, 2.365, 2.306, 2.262, 2.228, 2.201, 2.179, 2.160,
+    2.145, 2.131, 2.120, 2.110, 2.101, 2.093, 2.086, 2.080, 2.074, 2.069, 2.064, 2.060, 2.056,
+    2.052, 2.048, 2.045, 2.042,
+];
+/// Critical value of the normal distribution, used above 30 degrees of freedom.
+const Z_CRITICAL: f64 = 1.960;
+
+/// Timing statistics of one benchmark configuration.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Record {
+    pub mean: f64,
+    pub stddev: f64,
+    pub runs: usize,
+}
+
+/// Outcome of comparing a `Record` against its baseline.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Verdict {
+    Regression,
+    Improvement,
+    NoChange,
+}
+
+impl Record {
+    /// Compares `self` against `baseline` with Welch's t-test.
+    ///
+    /// The difference of the means is only reported when it is significant at
+    /// the 95% level, otherwise it is attributed to noise. Records of less
+    /// than 2 runs have no variance estimate and always give `NoChange`.
+    pub fn compare(&self, baseline: &Record) -> Verdict {
+        if self.runs < 2 || baseline.runs < 2 {
+            return Verdict::NoChange;
+        }
+        let v1 = self.stddev.powi(2) / self.runs as f64;
+        let v2 = baseline.stddev.powi(2) / baseline.runs as f64;
+        let diff = self.mean - baseline.mean;
+
+        let se = (v1 + v2).sqrt();
+        if se == 0f64 {
+            return match diff.partial_cmp(&0f64) {
+                Some(std::cmp::Ordering::Greater) => Verdict::Regression,
+                Some(std::cmp::Ordering::Less) => Verdict::Improvement,
+                _ => Verdict::NoChange,
+            };
+        }
+
+        // Welch–Satterthwaite approximation of the degrees of freedom
+        let df = (v1 + v2).powi(2)
+            / (v1.powi(2) / (self.runs - 1) as f64 + v2.powi(2) / (baseline.runs - 1) as f64);
+        let critical = T_CRITICAL
+            .get((df.floor() as usize).saturating_sub(1))
+            .copied()
+            .unwrap_or(Z_CRITICAL);
+
+        let t = diff / se;
+        if t > critical {
+            Verdict::Regression
+        } else if t < -critical {
+            Verdict::Improvement
+        } else {
+            Verdict::NoChange
+        }
+    }
+}
+
+/// Saved benchmark results, keyed by kernel name and matrix size.
+///
+/// Stored as JSON, e.g. `{"naive": {"256": {"mean": ..., "stddev": ..., "runs": 5}}}`.
+#[derive(Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct Baselines(BTreeMap<String, BTreeMap<usize, Record>>);
+
+impl Baselines {
+    /// Reads the baselines stored at `path`.
+    /// A missing file is treated as an empty store.
+    pub fn load<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        match fs::read_to_string(path) {
+            Ok(contents) => serde_json::from_str(&contents).map_err(io::Error::from),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(Self::default()),
+            Err(e) => Err(e),
+        }
+    }
+
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> io::Result<()> {
+        fs::write(path, serde_json::to_string_pretty(self)?)
+    }
+
+    pub fn get(&self, kernel: &str, size: usize) -> Option<&Record> {
+        self.0.get(kernel).and_then(|sizes| sizes.get(&size))
+    }
+
+    /// Records `record` as the baseline of `kernel` at `size`, replacing any
+    /// previous one.
+    pub fn insert(&mut self, kernel: &str, size: usize, record: Record) {
+        self.0
+            .entry(kernel.to_string())
+            .or_default()
+            .insert(size, record);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn record(mean: f64, stddev: f64) -> Record {
+        Record {
+            mean,
+            stddev,
+            runs: 5,
+        }
+    }
+
+    #[test]
+    fn compares() {
+        let baseline = record(1f64, 0.01);
+        assert_eq!(record(1.2, 0.01).compare(&baseline), Verdict::Regression);
+        assert_eq!(record(0.8, 0.01).compare(&baseline), Verdict::Improvement);
+        assert_eq!(record(1.005, 0.01).compare(&baseline), Verdict::NoChange);
+        // Same difference but too noisy to be significant
+        assert_eq!(record(1.2, 0.5).compare(&baseline), Verdict::NoChange);
+    }
+
+    #[test]
+    fn ignores_single_runs() {
+        let baseline = record(1f64, 0.01);
+        for runs in [0, 1] {
+            let single = Record {
+                mean: 2f64,
+                stddev: 0f64,
+                runs,
+            };
+            assert_eq!(single.compare(&baseline), Verdict::NoChange);
+            assert_eq!(baseline.compare(&single), Verdict::NoChange);
+        }
+    }
+
+    #[test]
+    fn round_trips() {
+        let path = std::env::temp_dir().join(format!("lab6-{}-baselines.json", std::process::id()));
+        let mut baselines = Baselines::load(&path).unwrap();
+        assert_eq!(baselines, Baselines::default());
+
+        baselines.insert("naive", 256, record(0.5, 0.01));
+        baselines.insert("naive", 512, record(4f64, 0.1));
+        baselines.save(&path).unwrap();
+
+        let loaded = Baselines::load(&path).unwrap();
+        assert_eq!(loaded.get("naive", 512), Some(&record(4f64, 0.1)));
+        assert_eq!(loaded.get("naive", 768), None);
+        assert_eq!(loaded, baselines);
+        std::fs::remove_file(&path).unwrap();
+    }
+}
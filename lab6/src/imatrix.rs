@@ -0,0 +1,229 @@
+use std::ops::{Index, IndexMut};
+
+type Integer = i64;
+
+/// Behaviour of integer arithmetic when a result does not fit in an `i64`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Overflow {
+    /// The operation fails and returns `None`.
+    Checked,
+    /// Results wrap around in two's complement.
+    Wrapping,
+    /// Results are clamped to `i64::MIN` or `i64::MAX`.
+    Saturating,
+    /// Results are reduced modulo `p`, into `0..p`.
+    Modulo(Integer),
+}
+
+impl Overflow {
+    /// Returns `acc + x * y` according to the policy. Saturating products are
+    /// not computed term by term, see `IMatrix::multiply_saturating`.
+    fn mul_add(self, acc: Integer, x: Integer, y: Integer) -> Option<Integer> {
+        match self {
+            Overflow::Checked => x.checked_mul(y).and_then(|xy| acc.checked_add(xy)),
+            Overflow::Wrapping => Some(acc.wrapping_add(x.wrapping_mul(y))),
+            Overflow::Saturating => unreachable!("see IMatrix::multiply_saturating"),
+            Overflow::Modulo(p) => {
+                let p = p as i128;
+                let xy = (x as i128 * y as i128).rem_euclid(p);
+                Some(((acc as i128 + xy).rem_euclid(p)) as Integer)
+            }
+        }
+    }
+}
+
+/// Square matrix of integers, for exact products.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct IMatrix {
+    n: usize,
+    values: Vec<Integer>,
+}
+
+impl IMatrix {
+    pub fn new(n: usize, values: Vec<Integer>) -> Self {
+        assert!(n != 0, "invalid dimension");
+        assert_eq!(n * n, values.len(), "invalid dimension");
+        Self { n, values }
+    }
+
+    pub fn zeroes(n: usize) -> Self {
+        assert!(n != 0, "invalid dimension");
+        Self {
+            n,
+            values: vec![0; n * n],
+        }
+    }
+
+    pub fn identity(n: usize) -> Self {
+        let mut m = Self::zeroes(n);
+        for i in 0..n {
+            m[(i, i)] = 1;
+        }
+
+        m
+    }
+
+    pub fn size(&self) -> usize {
+        self.n
+    }
+
+    /// Multiplies `a` by `b`, handling overflows according to `overflow`.
+    /// Returns `None` iff `overflow` is `Overflow::Checked` and an operation
+    /// overflowed.
+    pub fn multiply(a: &Self, b: &Self, overflow: Overflow) -> Option<Self> {
+        assert_eq!(a.n, b.n);
+        if let Overflow::Modulo(p) = overflow {
+            assert!(p > 0, "invalid modulus");
+        }
+        if overflow == Overflow::Saturating {
+            return Some(Self::multiply_saturating(a, b));
+        }
+        let mut c = Self::zeroes(a.n);
+
+        for i in 0..(c.n) {
+            for k in 0..(c.n) {
+                let loc = a[(i, k)];
+                for j in 0..(c.n) {
+                    c[(i, j)] = overflow.mul_add(c[(i, j)], loc, b[(k, j)])?;
+                }
+            }
+        }
+
+        Some(c)
+    }
+
+    /// Multiplies `a` by `b`, clamping each element once its exact value is
+    /// known: saturating the intermediate sums would make the result depend
+    /// on the order of the terms.
+    fn multiply_saturating(a: &Self, b: &Self) -> Self {
+        let mut c = Self::zeroes(a.n);
+
+        for i in 0..(c.n) {
+            for j in 0..(c.n) {
+                // Products fit in an i128, but their sum may not: count how
+                // many times the accumulator wrapped around, and in which way
+                let (mut acc, mut wraps) = (0i128, 0i64);
+                for k in 0..(c.n) {
+                    let xy = a[(i, k)] as i128 * b[(k, j)] as i128;
+                    let (sum, wrapped) = acc.overflowing_add(xy);
+                    if wrapped {
+                        wraps += if xy > 0 { 1 } else { -1 };
+                    }
+                    acc = sum;
+                }
+
+                c[(i, j)] = match wraps {
+                    0 => acc.clamp(Integer::MIN as i128, Integer::MAX as i128) as Integer,
+                    w if w > 0 => Integer::MAX,
+                    _ => Integer::MIN,
+                };
+            }
+        }
+
+        c
+    }
+
+    /// Raises `self` to the power `k` by repeated squaring, which takes
+    /// O(log k) calls to `multiply`.
+    pub fn pow(&self, mut k: u32, overflow: Overflow) -> Option<Self> {
+        let mut result = Self::identity(self.n);
+        if let Overflow::Modulo(1) = overflow {
+            result = Self::zeroes(self.n);
+        }
+        let mut base = self.clone();
+
+        while k > 0 {
+            if k & 1 == 1 {
+                result = Self::multiply(&result, &base, overflow)?;
+            }
+            k >>= 1;
+            if k > 0 {
+                base = Self::multiply(&base, &base, overflow)?;
+            }
+        }
+
+        Some(result)
+    }
+}
+
+impl Index<(usize, usize)> for IMatrix {
+    type Output = Integer;
+
+    fn index(&self, (i, j): (usize, usize)) -> &Self::Output {
+        &self.values[i * self.n + j]
+    }
+}
+
+impl IndexMut<(usize, usize)> for IMatrix {
+    fn index_mut(&mut self, (i, j): (usize, usize)) -> &mut Self::Output {
+        &mut self.values[i * self.n + j]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn multiplies() {
+        let a = IMatrix::new(2, vec![1, 2, 3, 4]);
+        let b = IMatrix::new(2, vec![5, 6, 7, 8]);
+        let c = IMatrix::multiply(&a, &b, Overflow::Checked).unwrap();
+        assert_eq!(c, IMatrix::new(2, vec![19, 22, 43, 50]));
+    }
+
+    #[test]
+    fn counts_paths() {
+        // Directed cycle 0 -> 1 -> 2 -> 0 plus the shortcut 0 -> 2
+        let adjacency = IMatrix::new(3, vec![0, 1, 1, 0, 0, 1, 1, 0, 0]);
+        let paths = adjacency.pow(3, Overflow::Checked).unwrap();
+        assert_eq!(paths[(0, 0)], 1);
+        assert_eq!(paths[(0, 2)], 1);
+        assert_eq!(
+            adjacency.pow(0, Overflow::Checked).unwrap(),
+            IMatrix::identity(3)
+        );
+    }
+
+    #[test]
+    fn handles_overflow() {
+        let m = IMatrix::new(1, vec![i64::MAX]);
+        let square = |overflow| IMatrix::multiply(&m, &m, overflow).map(|c| c[(0, 0)]);
+
+        assert_eq!(square(Overflow::Checked), None);
+        assert_eq!(square(Overflow::Wrapping), Some(1));
+        assert_eq!(square(Overflow::Saturating), Some(i64::MAX));
+        assert_eq!(square(Overflow::Modulo(1_000_000_007)), Some(737_564_071));
+    }
+
+    #[test]
+    fn saturates_results_only() {
+        // Row [MAX, -MAX] times column [2, 1] is MAX, although MAX * 2 is not
+        let a = IMatrix::new(2, vec![i64::MAX, -i64::MAX, 0, 0]);
+        let b = IMatrix::new(2, vec![2, 0, 1, 0]);
+        let c = IMatrix::multiply(&a, &b, Overflow::Saturating).unwrap();
+        assert_eq!(c, IMatrix::new(2, vec![i64::MAX, 0, 0, 0]));
+
+        // Sums beyond the range of an i128 are clamped too
+        let n = 5;
+        let a = IMatrix::new(n, vec![i64::MIN; n * n]);
+        let c = IMatrix::multiply(&a, &a, Overflow::Saturating).unwrap();
+        assert_eq!(c, IMatrix::new(n, vec![i64::MAX; n * n]));
+        let b = IMatrix::new(n, vec![i64::MAX; n * n]);
+        let c = IMatrix::multiply(&a, &b, Overflow::Saturating).unwrap();
+        assert_eq!(c, IMatrix::new(n, vec![i64::MIN; n * n]));
+    }
+
+    #[test]
+    fn computes_fibonacci_modulo() {
+        let fib = IMatrix::new(2, vec![1, 1, 1, 0]);
+        let p = 1_000_000_007;
+        assert_eq!(fib.pow(10, Overflow::Modulo(p)).unwrap()[(0, 1)], 55);
+        // F(1000) mod 1e9+7
+        assert_eq!(
+            fib.pow(1000, Overflow::Modulo(p)).unwrap()[(0, 1)],
+            517_691_607
+        );
+        assert_eq!(fib.pow(100, Overflow::Checked), None);
+    }
+}
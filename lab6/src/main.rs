@@ -1,34 +1,132 @@
+mod baseline;
+#[allow(dead_code)]
+mod imatrix;
+#[allow(dead_code)]
+mod mapped;
 #[allow(dead_code)]
-
 mod matrix;
+mod perf;
+#[allow(dead_code)]
+mod smatrix;
 extern crate statistical;
 
+use baseline::{Baselines, Record, Verdict};
 use matrix::Matrix;
+use perf::Counters;
 use std::time::Instant;
 
+/// Name of the benchmarked kernel in the baselines file.
+const KERNEL: &str = "naive";
+
 fn main() {
     let args: Vec<String> = std::env::args().collect();
-    if args.len() != 3 {
-        panic!("Usage: {} <size> <nb_runs>", args[0]);
+    let usage = format!(
+        "Usage: {} <size> <nb_runs> [--perf] [--save <baselines.json>] [--compare <baselines.json>]",
+        args[0]
+    );
+    if args.len() < 3 {
+        panic!("{}", usage);
     }
 
     let n = args[1].parse::<usize>().unwrap();
     let runs = args[2].parse::<usize>().unwrap();
 
+    let mut with_perf = false;
+    let mut save = None;
+    let mut compare = None;
+    let mut options = args[3..].iter();
+    while let Some(option) = options.next() {
+        match option.as_str() {
+            "--perf" => with_perf = true,
+            "--save" => save = Some(options.next().expect(&usage)),
+            "--compare" => compare = Some(options.next().expect(&usage)),
+            _ => panic!("{}", usage),
+        }
+    }
+
+    // Fall back to timings only when the kernel denies access to the counters
+    let mut counters = if with_perf {
+        match Counters::open() {
+            Ok(counters) => Some(counters),
+            Err(e) => {
+                eprintln!(
+                    "warning: hardware counters unavailable ({}), timings only",
+                    e
+                );
+                None
+            }
+        }
+    } else {
+        None
+    };
+
     let mut times = Vec::with_capacity(runs);
+    let mut counts = Vec::with_capacity(runs);
     let a = Matrix::random(n);
     let b = Matrix::random(n);
 
     for _ in 0..runs {
+        if let Some(c) = counters.as_mut() {
+            c.start().expect("Failed to start counters");
+        }
         let t = Instant::now();
         let _ = Matrix::multiply(&a, &b);
         times.push(t.elapsed());
+        if let Some(c) = counters.as_mut() {
+            counts.push(c.stop().expect("Failed to read counters"));
+        }
     }
 
     let times: Vec<f64> = times.iter().map(|d| d.as_secs_f64()).collect();
     let mean = statistical::mean(&times);
     let stddev = statistical::standard_deviation(&times, None);
-    println!("Size\tMean time (s)\tStandard deviation (s)");
-    println!(
-        "{}\t{:.6}\t{:.6}\t", n, mean, stddev);
+
+    print!("Size\tMean time (s)\tStandard deviation (s)");
+    if let Some(c) = &counters {
+        for event in c.events() {
+            print!("\tMean {}", event.name());
+        }
+    }
+    println!();
+
+    print!("{}\t{:.6}\t{:.6}\t", n, mean, stddev);
+    if let Some(c) = &counters {
+        for i in 0..c.events().count() {
+            let total: u64 = counts.iter().map(|run| run[i]).sum();
+            print!("{}\t", total / runs as u64);
+        }
+    }
+    println!();
+
+    let record = Record { mean, stddev, runs };
+    if let Some(path) = compare {
+        let baselines = Baselines::load(path).expect("Failed to load baselines");
+        // Reported on stderr to keep stdout parsable by `bench.sh`
+        match baselines.get(KERNEL, n) {
+            Some(baseline) => {
+                let verdict = match record.compare(baseline) {
+                    Verdict::Regression => "REGRESSION",
+                    Verdict::Improvement => "improvement",
+                    Verdict::NoChange => "no significant change",
+                };
+                eprintln!(
+                    "{} {}x{}: {:.6}s (baseline {:.6}s, {:+.1}%): {}",
+                    KERNEL,
+                    n,
+                    n,
+                    mean,
+                    baseline.mean,
+                    (mean / baseline.mean - 1f64) * 100f64,
+                    verdict
+                );
+            }
+            None => eprintln!("{} {}x{}: no baseline", KERNEL, n, n),
+        }
+    }
+
+    if let Some(path) = save {
+        let mut baselines = Baselines::load(path).expect("Failed to load baselines");
+        baselines.insert(KERNEL, n, record);
+        baselines.save(path).expect("Failed to save baselines");
+    }
 }
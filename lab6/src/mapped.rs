@@ -0,0 +1,435 @@
+use crate::matrix::Matrix;
+use std::fs::{File, OpenOptions};
+use std::io::{self, Read, Write};
+use std::ops::{Index, IndexMut};
+use std::os::unix::fs::MetadataExt;
+use std::os::unix::io::AsRawFd;
+use std::path::Path;
+
+type Element = f64;
+
+/// Magic bytes at the start of every matrix file.
+const MAGIC: [u8; 8] = *b"MATF64LE";
+/// Magic bytes followed by the dimension `n` as a little-endian `u64`.
+const HEADER_LEN: usize = 16;
+
+/// Square matrix stored in a memory-mapped file.
+///
+/// The file starts with a 16 bytes header (`MAGIC` and `n`) followed by the
+/// `n * n` elements in row major order, as little-endian `f64`s.
+/// Pages are loaded and written back by the kernel on demand, which lets us
+/// work on matrices that do not fit in RAM.
+///
+/// The elements are borrowed straight from the mapping, which Rust can not
+/// protect from other writers of the file: every constructor is `unsafe`,
+/// see `create` for the invariants the caller must uphold.
+#[derive(Debug)]
+pub struct MappedMatrix {
+    n: usize,
+    ptr: *mut u8,
+    len: usize,
+    /// Device and inode numbers of the mapped file.
+    file_id: (u64, u64),
+    /// False when the file is mapped read-only, see `open_read_only`.
+    writable: bool,
+}
+
+impl MappedMatrix {
+    /// Creates (or truncates) the file at `path` and maps a zero-filled
+    /// matrix of dimension `n`.
+    ///
+    /// # Safety
+    ///
+    /// While the returned matrix is alive, the file must not be modified,
+    /// truncated or mapped again, by this process or another, except through
+    /// read-only mappings when this one is read-only too. Otherwise the slices
+    /// returned by `values` and `values_mut` could change while borrowed, or
+    /// accessing them could raise `SIGBUS`.
+    pub unsafe fn create<P: AsRef<Path>>(path: P, n: usize) -> io::Result<Self> {
+        assert!(n != 0, "invalid dimension");
+        let len = file_len(n)?;
+
+        let mut file = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create(true)
+            .truncate(true)
+            .open(path)?;
+        file.write_all(&MAGIC)?;
+        file.write_all(&(n as u64).to_le_bytes())?;
+        // Extending the file fills it with zeroes without touching the disk
+        file.set_len(len as u64)?;
+
+        Self::map(&file, n, len, true)
+    }
+
+    /// Maps an existing matrix file for reading and writing, checking its
+    /// header and length.
+    ///
+    /// # Safety
+    ///
+    /// See `create`.
+    pub unsafe fn open<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        Self::open_with(path, true)
+    }
+
+    /// Maps an existing matrix file for reading only, checking its header and
+    /// length. The pages are mapped with `PROT_READ`: `values_mut` and
+    /// `IndexMut` panic.
+    ///
+    /// # Safety
+    ///
+    /// See `create`.
+    pub unsafe fn open_read_only<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        Self::open_with(path, false)
+    }
+
+    fn open_with<P: AsRef<Path>>(path: P, writable: bool) -> io::Result<Self> {
+        let mut file = OpenOptions::new().read(true).write(writable).open(path)?;
+
+        let mut header = [0u8; HEADER_LEN];
+        file.read_exact(&mut header).map_err(|e| match e.kind() {
+            io::ErrorKind::UnexpectedEof => invalid_data("file too short"),
+            _ => e,
+        })?;
+        if header[..8] != MAGIC {
+            return Err(invalid_data("not a matrix file"));
+        }
+
+        let n = u64::from_le_bytes(header[8..].try_into().unwrap()) as usize;
+        if n == 0 {
+            return Err(invalid_data("invalid dimension"));
+        }
+        let len = file_len(n)?;
+        if file.metadata()?.len() != len as u64 {
+            return Err(invalid_data("file length does not match dimension"));
+        }
+
+        Self::map(&file, n, len, writable)
+    }
+
+    /// Writes `m` to a new matrix file at `path`.
+    ///
+    /// # Safety
+    ///
+    /// See `create`.
+    pub unsafe fn from_matrix<P: AsRef<Path>>(path: P, m: &Matrix) -> io::Result<Self> {
+        let n = m.size();
+        let mut mapped = Self::create(path, n)?;
+        for i in 0..n {
+            for j in 0..n {
+                mapped[(i, j)] = m[(i, j)];
+            }
+        }
+
+        Ok(mapped)
+    }
+
+    /// Copies the mapped matrix into memory.
+    pub fn to_matrix(&self) -> Matrix {
+        Matrix::new(self.n, self.values().to_vec())
+    }
+
+    pub fn size(&self) -> usize {
+        self.n
+    }
+
+    pub fn values(&self) -> &[Element] {
+        // SAFETY: the mapping is page aligned and `HEADER_LEN` is a multiple
+        // of `size_of::<Element>()`, so the elements are properly aligned and
+        // `len` guarantees there are `n * n` of them.
+        unsafe {
+            std::slice::from_raw_parts(self.ptr.add(HEADER_LEN) as *const Element, self.n * self.n)
+        }
+    }
+
+    /// Panics if the matrix is mapped read-only.
+    pub fn values_mut(&mut self) -> &mut [Element] {
+        assert!(self.writable, "matrix is mapped read-only");
+        // SAFETY: see `values`, and `&mut self` guarantees exclusive access.
+        unsafe {
+            std::slice::from_raw_parts_mut(
+                self.ptr.add(HEADER_LEN) as *mut Element,
+                self.n * self.n,
+            )
+        }
+    }
+
+    /// Synchronously writes the dirty pages back to the file.
+    pub fn flush(&self) -> io::Result<()> {
+        // SAFETY: `ptr` and `len` describe a live mapping owned by `self`.
+        if unsafe { libc::msync(self.ptr as *mut libc::c_void, self.len, libc::MS_SYNC) } != 0 {
+            return Err(io::Error::last_os_error());
+        }
+
+        Ok(())
+    }
+
+    /// Multiplies `a` by `b` out-of-core, writing the result in a new matrix
+    /// file at `path`.
+    ///
+    /// The loops are blocked into `tile * tile` sub-matrices so that only
+    /// three tiles need to be resident at once: the kernel streams the tiles
+    /// of `a` and `b` in from their files and writes the tiles of the result
+    /// back as memory pressure requires.
+    ///
+    /// `path` must not be the file of `a` or `b`: creating the result would
+    /// truncate it under their mapping.
+    ///
+    /// # Safety
+    ///
+    /// See `create`, for the file at `path`.
+    pub unsafe fn multiply_tiled<P: AsRef<Path>>(
+        a: &Self,
+        b: &Self,
+        path: P,
+        tile: usize,
+    ) -> io::Result<Self> {
+        assert_eq!(a.n, b.n);
+        assert!(tile != 0, "invalid tile size");
+        let n = a.n;
+        // The output may not exist yet, in which case it is not an input
+        if let Ok(meta) = std::fs::metadata(&path) {
+            let id = (meta.dev(), meta.ino());
+            if id == a.file_id || id == b.file_id {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    "output file is one of the inputs",
+                ));
+            }
+        }
+        let mut c = Self::create(path, n)?;
+
+        // Each row block of `a` and of `c` is visited once, in order
+        a.advise(libc::MADV_SEQUENTIAL);
+        c.advise(libc::MADV_SEQUENTIAL);
+
+        let (av, bv) = (a.values(), b.values());
+        let cv = c.values_mut();
+        for ii in (0..n).step_by(tile) {
+            let i_end = (ii + tile).min(n);
+            for kk in (0..n).step_by(tile) {
+                let k_end = (kk + tile).min(n);
+                for jj in (0..n).step_by(tile) {
+                    let j_end = (jj + tile).min(n);
+                    for i in ii..i_end {
+                        for k in kk..k_end {
+                            let loc = av[i * n + k];
+                            for j in jj..j_end {
+                                cv[i * n + j] += loc * bv[k * n + j];
+                            }
+                        }
+                    }
+                }
+            }
+        }
+
+        c.flush()?;
+        Ok(c)
+    }
+
+    fn map(file: &File, n: usize, len: usize, writable: bool) -> io::Result<Self> {
+        let meta = file.metadata()?;
+        let file_id = (meta.dev(), meta.ino());
+
+        let prot = if writable {
+            libc::PROT_READ | libc::PROT_WRITE
+        } else {
+            libc::PROT_READ
+        };
+        // SAFETY: we ask for a fresh shared mapping of the whole file, which
+        // does not alias any Rust memory. The file descriptor may be closed
+        // afterwards, the mapping stays valid until `munmap`.
+        let ptr = unsafe {
+            libc::mmap(
+                std::ptr::null_mut(),
+                len,
+                prot,
+                libc::MAP_SHARED,
+                file.as_raw_fd(),
+                0,
+            )
+        };
+        if ptr == libc::MAP_FAILED {
+            return Err(io::Error::last_os_error());
+        }
+
+        Ok(Self {
+            n,
+            ptr: ptr as *mut u8,
+            len,
+            file_id,
+            writable,
+        })
+    }
+
+    /// Hints the kernel about the access pattern. Failures are harmless.
+    fn advise(&self, advice: libc::c_int) {
+        // SAFETY: `ptr` and `len` describe a live mapping owned by `self`.
+        unsafe {
+            libc::madvise(self.ptr as *mut libc::c_void, self.len, advice);
+        }
+    }
+}
+
+impl Drop for MappedMatrix {
+    fn drop(&mut self) {
+        // SAFETY: the mapping was created in `map` and is never used again.
+        unsafe {
+            libc::munmap(self.ptr as *mut libc::c_void, self.len);
+        }
+    }
+}
+
+impl Index<(usize, usize)> for MappedMatrix {
+    type Output = Element;
+
+    fn index(&self, (i, j): (usize, usize)) -> &Self::Output {
+        &self.values()[i * self.n + j]
+    }
+}
+
+impl IndexMut<(usize, usize)> for MappedMatrix {
+    fn index_mut(&mut self, (i, j): (usize, usize)) -> &mut Self::Output {
+        let n = self.n;
+        &mut self.values_mut()[i * n + j]
+    }
+}
+
+/// Returns the length in bytes of the file holding a matrix of dimension `n`.
+fn file_len(n: usize) -> io::Result<usize> {
+    n.checked_mul(n)
+        .and_then(|len| len.checked_mul(std::mem::size_of::<Element>()))
+        .and_then(|len| len.checked_add(HEADER_LEN))
+        .ok_or_else(|| invalid_data("dimension too large"))
+}
+
+fn invalid_data(msg: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, msg)
+}
+
+// The file format is little-endian and elements are accessed in place
+#[cfg(not(target_endian = "little"))]
+compile_error!("memory-mapped matrices require a little-endian target");
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::panic::{self, AssertUnwindSafe};
+    use std::path::PathBuf;
+
+    // SAFETY, for every mapping of the tests: the files are named after the
+    // process and the test, nothing else touches them.
+
+    fn temp_path(name: &str) -> PathBuf {
+        std::env::temp_dir().join(format!("lab6-{}-{}.mat", std::process::id(), name))
+    }
+
+    #[test]
+    fn creates_zeroes() {
+        let path = temp_path("creates_zeroes");
+        let m = unsafe { MappedMatrix::create(&path, 3) }.unwrap();
+        assert_eq!(m.size(), 3);
+        assert_eq!(m.to_matrix(), Matrix::zeroes(3));
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn round_trips() {
+        let path = temp_path("round_trips");
+        let a = Matrix::random(5);
+        {
+            let mut m = unsafe { MappedMatrix::from_matrix(&path, &a) }.unwrap();
+            m[(1, 2)] = 42f64;
+            m.flush().unwrap();
+        }
+
+        let m = unsafe { MappedMatrix::open(&path) }.unwrap();
+        assert_eq!(m[(1, 2)], 42f64);
+        assert_eq!(m[(4, 3)], a[(4, 3)]);
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn opens_read_only() {
+        let path = temp_path("opens_read_only");
+        let a = Matrix::random(4);
+        drop(unsafe { MappedMatrix::from_matrix(&path, &a) }.unwrap());
+
+        // Read permission is enough
+        let mut perms = std::fs::metadata(&path).unwrap().permissions();
+        perms.set_readonly(true);
+        std::fs::set_permissions(&path, perms).unwrap();
+
+        let mut m = unsafe { MappedMatrix::open_read_only(&path) }.unwrap();
+        assert_eq!(m.to_matrix(), a);
+        let write = panic::catch_unwind(AssertUnwindSafe(|| m[(0, 0)] = 1f64));
+        assert!(write.is_err());
+        assert_eq!(m[(0, 0)], a[(0, 0)]);
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn rejects_invalid_files() {
+        let path = temp_path("rejects_invalid_files");
+        std::fs::write(&path, b"definitely not a matrix").unwrap();
+        let err = unsafe { MappedMatrix::open(&path) }.unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+
+        // Shorter than the header
+        std::fs::write(&path, b"MATF64").unwrap();
+        let err = unsafe { MappedMatrix::open_read_only(&path) }.unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn multiplies_tiled() {
+        let (pa, pb, pc) = (temp_path("mt_a"), temp_path("mt_b"), temp_path("mt_c"));
+        let a = Matrix::random(7);
+        let b = Matrix::random(7);
+        let (ma, mb) = unsafe {
+            MappedMatrix::from_matrix(&pa, &a).unwrap();
+            MappedMatrix::from_matrix(&pb, &b).unwrap();
+            (
+                MappedMatrix::open_read_only(&pa).unwrap(),
+                MappedMatrix::open_read_only(&pb).unwrap(),
+            )
+        };
+
+        // A tile size that does not divide `n` exercises the partial tiles
+        let mc = unsafe { MappedMatrix::multiply_tiled(&ma, &mb, &pc, 3) }.unwrap();
+        let c = Matrix::multiply(&a, &b);
+        for i in 0..7 {
+            for j in 0..7 {
+                assert!((mc[(i, j)] - c[(i, j)]).abs() < 1e-12);
+            }
+        }
+
+        for p in [pa, pb, pc] {
+            std::fs::remove_file(p).unwrap();
+        }
+    }
+
+    #[test]
+    fn rejects_input_as_output() {
+        let (pa, pb) = (temp_path("rio_a"), temp_path("rio_b"));
+        let a = Matrix::random(4);
+        let ma = unsafe { MappedMatrix::from_matrix(&pa, &a) }.unwrap();
+        let mb = unsafe { MappedMatrix::from_matrix(&pb, &a) }.unwrap();
+
+        // Also through another path to the same file
+        let alias = pb.parent().unwrap().join(".").join(pb.file_name().unwrap());
+        for out in [&pa, &alias] {
+            let err = unsafe { MappedMatrix::multiply_tiled(&ma, &mb, out, 2) }.unwrap_err();
+            assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+        }
+        // The inputs are left untouched
+        assert_eq!(ma.to_matrix(), a);
+        assert_eq!(mb.to_matrix(), a);
+
+        for p in [pa, pb] {
+            std::fs::remove_file(p).unwrap();
+        }
+    }
+}
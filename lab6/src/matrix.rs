@@ -54,6 +54,10 @@ impl Matrix {
         Self::new(n, values)
     }
 
+    pub fn size(&self) -> usize {
+        self.n
+    }
+
     pub fn multiply(a: &Self, b: &Self) -> Self {
         assert_eq!(a.n, b.n);
         let mut c = Self::zeroes(a.n);
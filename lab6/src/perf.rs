@@ -0,0 +1,223 @@
+use std::fs::File;
+use std::io::{self, Read};
+use std::os::unix::io::FromRawFd;
+
+// Constants from `linux/perf_event.h`
+const PERF_TYPE_HARDWARE: u32 = 0;
+const PERF_TYPE_HW_CACHE: u32 = 3;
+const PERF_COUNT_HW_CPU_CYCLES: u64 = 0;
+const PERF_COUNT_HW_INSTRUCTIONS: u64 = 1;
+const PERF_COUNT_HW_CACHE_L1D: u64 = 0;
+const PERF_COUNT_HW_CACHE_LL: u64 = 2;
+const PERF_COUNT_HW_CACHE_OP_READ: u64 = 0;
+const PERF_COUNT_HW_CACHE_RESULT_MISS: u64 = 1;
+const PERF_FORMAT_TOTAL_TIME_ENABLED: u64 = 1 << 0;
+const PERF_FORMAT_TOTAL_TIME_RUNNING: u64 = 1 << 1;
+const ATTR_DISABLED: u64 = 1 << 0;
+const ATTR_EXCLUDE_KERNEL: u64 = 1 << 5;
+const ATTR_EXCLUDE_HV: u64 = 1 << 6;
+const PERF_ATTR_SIZE_VER0: u32 = 64;
+const PERF_EVENT_IOC_ENABLE: libc::c_ulong = 0x2400;
+const PERF_EVENT_IOC_DISABLE: libc::c_ulong = 0x2401;
+const PERF_EVENT_IOC_RESET: libc::c_ulong = 0x2403;
+const PERF_FLAG_FD_CLOEXEC: libc::c_ulong = 1 << 3;
+
+/// First version of `struct perf_event_attr`, which every kernel accepts.
+/// The bitfield of flags is represented as a single `u64`.
+#[repr(C)]
+#[derive(Default)]
+struct PerfEventAttr {
+    kind: u32,
+    size: u32,
+    config: u64,
+    sample_period: u64,
+    sample_type: u64,
+    read_format: u64,
+    flags: u64,
+    wakeup_events: u32,
+    bp_type: u32,
+    config1: u64,
+}
+
+/// Hardware events recorded by `Counters`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Event {
+    Cycles,
+    Instructions,
+    L1dMisses,
+    LlcMisses,
+}
+
+impl Event {
+    pub const ALL: [Event; 4] = [
+        Event::Cycles,
+        Event::Instructions,
+        Event::L1dMisses,
+        Event::LlcMisses,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Event::Cycles => "cycles",
+            Event::Instructions => "instructions",
+            Event::L1dMisses => "L1-dcache-load-misses",
+            Event::LlcMisses => "LLC-load-misses",
+        }
+    }
+
+    fn kind_and_config(self) -> (u32, u64) {
+        let cache_miss = |cache| {
+            cache | (PERF_COUNT_HW_CACHE_OP_READ << 8) | (PERF_COUNT_HW_CACHE_RESULT_MISS << 16)
+        };
+        match self {
+            Event::Cycles => (PERF_TYPE_HARDWARE, PERF_COUNT_HW_CPU_CYCLES),
+            Event::Instructions => (PERF_TYPE_HARDWARE, PERF_COUNT_HW_INSTRUCTIONS),
+            Event::L1dMisses => (PERF_TYPE_HW_CACHE, cache_miss(PERF_COUNT_HW_CACHE_L1D)),
+            Event::LlcMisses => (PERF_TYPE_HW_CACHE, cache_miss(PERF_COUNT_HW_CACHE_LL)),
+        }
+    }
+}
+
+/// In-process hardware performance counters, using `perf_event_open(2)`.
+///
+/// Only user space is counted, so that the counters remain available with
+/// `perf_event_paranoid` set to 2 (the default on most distributions).
+pub struct Counters {
+    events: Vec<(Event, File)>,
+}
+
+impl Counters {
+    /// Opens a counter for every supported event of `Event::ALL`.
+    ///
+    /// Events the CPU does not support are skipped. An error is returned when
+    /// no counter could be opened at all, e.g. when the kernel denies access.
+    pub fn open() -> io::Result<Self> {
+        let mut events = Vec::new();
+        let mut last_err = None;
+        for event in Event::ALL {
+            match open_event(event) {
+                Ok(file) => events.push((event, file)),
+                Err(e) => last_err = Some(e),
+            }
+        }
+
+        match last_err {
+            Some(e) if events.is_empty() => Err(e),
+            _ => Ok(Self { events }),
+        }
+    }
+
+    /// Returns the events being counted.
+    pub fn events(&self) -> impl Iterator<Item = Event> + '_ {
+        self.events.iter().map(|(event, _)| *event)
+    }
+
+    /// Resets the counters to zero and starts counting.
+    pub fn start(&mut self) -> io::Result<()> {
+        for (_, file) in &self.events {
+            ioctl(file, PERF_EVENT_IOC_RESET)?;
+            ioctl(file, PERF_EVENT_IOC_ENABLE)?;
+        }
+
+        Ok(())
+    }
+
+    /// Stops counting and returns the value of each counter, in the order of
+    /// `events`.
+    ///
+    /// When the kernel had to multiplex the counters, values are scaled by the
+    /// fraction of time each counter was actually running.
+    pub fn stop(&mut self) -> io::Result<Vec<u64>> {
+        for (_, file) in &self.events {
+            ioctl(file, PERF_EVENT_IOC_DISABLE)?;
+        }
+
+        let mut values = Vec::with_capacity(self.events.len());
+        for (_, file) in &mut self.events {
+            // `value`, `time_enabled`, `time_running`, see `read_format`
+            let mut buf = [0u8; 24];
+            file.read_exact(&mut buf)?;
+            let field = |i: usize| u64::from_ne_bytes(buf[i * 8..(i + 1) * 8].try_into().unwrap());
+            let (value, enabled, running) = (field(0), field(1), field(2));
+
+            values.push(if running == 0 {
+                0
+            } else {
+                (value as u128 * enabled as u128 / running as u128) as u64
+            });
+        }
+
+        Ok(values)
+    }
+}
+
+fn open_event(event: Event) -> io::Result<File> {
+    let (kind, config) = event.kind_and_config();
+    let attr = PerfEventAttr {
+        kind,
+        size: PERF_ATTR_SIZE_VER0,
+        config,
+        read_format: PERF_FORMAT_TOTAL_TIME_ENABLED | PERF_FORMAT_TOTAL_TIME_RUNNING,
+        flags: ATTR_DISABLED | ATTR_EXCLUDE_KERNEL | ATTR_EXCLUDE_HV,
+        ..Default::default()
+    };
+
+    // SAFETY: `attr` is a valid `perf_event_attr` of the advertised size.
+    // We monitor the calling thread (pid 0) on any CPU (-1), without group.
+    let fd = unsafe {
+        libc::syscall(
+            libc::SYS_perf_event_open,
+            &attr as *const PerfEventAttr,
+            0,
+            -1,
+            -1,
+            PERF_FLAG_FD_CLOEXEC,
+        )
+    };
+    if fd < 0 {
+        return Err(io::Error::last_os_error());
+    }
+
+    // SAFETY: `fd` is a freshly opened descriptor that nobody else owns.
+    Ok(unsafe { File::from_raw_fd(fd as libc::c_int) })
+}
+
+fn ioctl(file: &File, request: libc::c_ulong) -> io::Result<()> {
+    use std::os::unix::io::AsRawFd;
+
+    // SAFETY: perf ioctls without argument on a perf event descriptor.
+    if unsafe { libc::ioctl(file.as_raw_fd(), request as _, 0) } != 0 {
+        return Err(io::Error::last_os_error());
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn counts_or_falls_back() {
+        let mut counters = match Counters::open() {
+            Ok(counters) => counters,
+            // Access denied or no PMU (e.g. in a container): nothing to check
+            Err(_) => return,
+        };
+
+        counters.start().unwrap();
+        let mut acc = 0u64;
+        for i in 0..100_000u64 {
+            acc = acc.wrapping_add(std::hint::black_box(i));
+        }
+        std::hint::black_box(acc);
+        let values = counters.stop().unwrap();
+
+        assert_eq!(values.len(), counters.events().count());
+        for (event, value) in counters.events().zip(&values) {
+            if event == Event::Instructions {
+                assert!(*value > 100_000);
+            }
+        }
+    }
+}
@@ -0,0 +1,178 @@
+use crate::matrix::Matrix;
+use rand::distributions::{Distribution, Uniform};
+use std::ops::{Index, IndexMut};
+
+type Element = f64;
+
+/// Stack-allocated `R x C` matrix, stored in row major order.
+///
+/// Meant for small transformations (2x2 up to 8x8) where allocating the `Vec`
+/// of a `Matrix` would cost more than the arithmetic itself.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct SMatrix<const R: usize, const C: usize> {
+    values: [[Element; C]; R],
+}
+
+impl<const R: usize, const C: usize> SMatrix<R, C> {
+    pub fn new(values: [[Element; C]; R]) -> Self {
+        assert!(R != 0 && C != 0, "invalid dimension");
+        Self { values }
+    }
+
+    pub fn from_value(value: Element) -> Self {
+        Self::new([[value; C]; R])
+    }
+
+    pub fn zeroes() -> Self {
+        Self::from_value(0f64)
+    }
+
+    pub fn ones() -> Self {
+        Self::from_value(1f64)
+    }
+
+    pub fn random() -> Self {
+        let mut rng = rand::thread_rng();
+        let between = Uniform::new_inclusive(-1f64, 1f64);
+
+        let mut m = Self::zeroes();
+        for row in m.values.iter_mut() {
+            for v in row.iter_mut() {
+                *v = between.sample(&mut rng);
+            }
+        }
+
+        m
+    }
+
+    /// Multiplies `a` by `b`.
+    ///
+    /// All loop bounds are compile time constants, which lets the compiler
+    /// fully unroll the loops for small dimensions.
+    pub fn multiply<const K: usize>(a: &Self, b: &SMatrix<C, K>) -> SMatrix<R, K> {
+        let mut c = SMatrix::<R, K>::zeroes();
+
+        for i in 0..R {
+            for k in 0..C {
+                let loc = a.values[i][k];
+                for j in 0..K {
+                    c.values[i][j] += loc * b.values[k][j];
+                }
+            }
+        }
+
+        c
+    }
+}
+
+impl<const N: usize> SMatrix<N, N> {
+    pub fn eye(value: Element) -> Self {
+        let mut m = Self::zeroes();
+        for i in 0..N {
+            m[(i, i)] = value;
+        }
+
+        m
+    }
+
+    pub fn identity() -> Self {
+        Self::eye(1f64)
+    }
+}
+
+impl<const R: usize, const C: usize> Index<(usize, usize)> for SMatrix<R, C> {
+    type Output = Element;
+
+    fn index(&self, (i, j): (usize, usize)) -> &Self::Output {
+        &self.values[i][j]
+    }
+}
+
+impl<const R: usize, const C: usize> IndexMut<(usize, usize)> for SMatrix<R, C> {
+    fn index_mut(&mut self, (i, j): (usize, usize)) -> &mut Self::Output {
+        &mut self.values[i][j]
+    }
+}
+
+impl<const N: usize> From<SMatrix<N, N>> for Matrix {
+    fn from(m: SMatrix<N, N>) -> Self {
+        Matrix::new(N, m.values.iter().flatten().copied().collect())
+    }
+}
+
+impl<const N: usize> TryFrom<&Matrix> for SMatrix<N, N> {
+    /// The dimension of the `Matrix` that could not be converted.
+    type Error = usize;
+
+    fn try_from(m: &Matrix) -> Result<Self, Self::Error> {
+        if m.size() != N {
+            return Err(m.size());
+        }
+
+        let mut s = Self::zeroes();
+        for i in 0..N {
+            for j in 0..N {
+                s[(i, j)] = m[(i, j)];
+            }
+        }
+
+        Ok(s)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn builds() {
+        let m = SMatrix::new([[1f64, 2f64, 3f64], [4f64, 5f64, 6f64]]);
+        assert_eq!(m[(0, 2)], 3f64);
+        assert_eq!(m[(1, 0)], 4f64);
+    }
+
+    #[test]
+    fn builds_identity() {
+        let m = SMatrix::<2, 2>::identity();
+        assert_eq!(m[(0, 0)], 1f64);
+        assert_eq!(m[(0, 1)], 0f64);
+        assert_eq!(m[(1, 0)], 0f64);
+        assert_eq!(m[(1, 1)], 1f64);
+    }
+
+    #[test]
+    fn multiplies() {
+        let a = SMatrix::new([[1f64, 2f64, 3f64], [4f64, 5f64, 6f64]]);
+        let b = SMatrix::new([[7f64, 8f64], [9f64, 10f64], [11f64, 12f64]]);
+        let c = SMatrix::multiply(&a, &b);
+
+        assert_eq!(c, SMatrix::new([[58f64, 64f64], [139f64, 154f64]]));
+    }
+
+    #[test]
+    fn multiplies_like_matrix() {
+        let a = SMatrix::<4, 4>::random();
+        let b = SMatrix::<4, 4>::random();
+        let c = Matrix::multiply(&Matrix::from(a), &Matrix::from(b));
+
+        assert_eq!(Matrix::from(SMatrix::multiply(&a, &b)), c);
+    }
+
+    #[test]
+    fn converts() {
+        let m = Matrix::random(3);
+        let s = SMatrix::<3, 3>::try_from(&m).unwrap();
+        assert_eq!(Matrix::from(s), m);
+        assert_eq!(SMatrix::<2, 2>::try_from(&m), Err(3));
+    }
+
+    #[test]
+    fn indexes() {
+        let mut m = SMatrix::new([[1f64, 2f64], [3f64, 4f64]]);
+        assert_eq!(m[(0, 0)], 1f64);
+        assert_eq!(m[(1, 0)], 3f64);
+
+        m[(1, 0)] = 5f64;
+        assert_eq!(m[(1, 0)], 5f64);
+    }
+}